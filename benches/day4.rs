@@ -0,0 +1,70 @@
+//! Day 4 searches on a large generated grid, in one band and in parallel
+//! bands. `DAY4_BENCH_SIZE` sets the grid's side, 10000 by default.
+
+use std::env;
+
+use advent_of_code_2024::{
+    grid::Grid,
+    pattern::Pattern,
+    transform::Transform,
+    word_search::{find_words, ROWS_PER_BAND},
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// Square byte grid of pseudo-random `XMAS` letters.
+fn generate_grid(size: usize) -> Grid<u8> {
+    let mut seed = 4_u64;
+    let cells = (0..size * size)
+        .map(|_| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            b"XMAS"[(seed >> 62) as usize]
+        })
+        .collect();
+    Grid::from_vec(size, size, cells)
+}
+
+fn day4(c: &mut Criterion) {
+    let size = env::var("DAY4_BENCH_SIZE")
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .unwrap_or(10_000);
+    let grid = generate_grid(size);
+    let words = [b"XMAS"];
+    let x_mas = Pattern::parse("M.S / .A. / M.S").map(|char| *char as u8);
+    let bands = [("one band", size), ("parallel", ROWS_PER_BAND)];
+
+    assert_eq!(
+        find_words(&grid, &words, size),
+        find_words(&grid, &words, ROWS_PER_BAND)
+    );
+    assert_eq!(
+        x_mas.find_all(&grid, &Transform::ROTATIONS),
+        x_mas.find_all_in_bands(&grid, &Transform::ROTATIONS, ROWS_PER_BAND)
+    );
+
+    let mut group = c.benchmark_group(format!("day4 {}x{}", size, size));
+    group.sample_size(10);
+    for (name, rows_per_band) in bands {
+        group.bench_with_input(
+            BenchmarkId::new("XMAS", name),
+            &rows_per_band,
+            |b, &rows| b.iter(|| find_words(&grid, &words, rows).len()),
+        );
+    }
+    group.bench_function(BenchmarkId::new("X-MAS", "one band"), |b| {
+        b.iter(|| x_mas.find_all(&grid, &Transform::ROTATIONS).len())
+    });
+    group.bench_function(BenchmarkId::new("X-MAS", "parallel"), |b| {
+        b.iter(|| {
+            x_mas
+                .find_all_in_bands(&grid, &Transform::ROTATIONS, ROWS_PER_BAND)
+                .len()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, day4);
+criterion_main!(benches);
@@ -0,0 +1,232 @@
+//! Bit-packed boolean grid for wall maps and visited sets.
+
+use std::fmt;
+
+use crate::grid::Bounds;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Rectangular grid of booleans addressed by `(y, x)`, one bit per cell.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    height: usize,
+    width: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn new(height: usize, width: usize) -> Self {
+        Self {
+            height,
+            width,
+            words: vec![0; (height * width).div_ceil(WORD_BITS)],
+        }
+    }
+
+    /// Builds a grid from puzzle input, setting the cells whose char matches `predicate`.
+    pub fn parse(input: &str, mut predicate: impl FnMut(char) -> bool) -> Self {
+        let height = input.lines().count();
+        let width = input.lines().next().map_or(0, |line| line.chars().count());
+        let mut grid = Self::new(height, width);
+        for (y, line) in input.lines().enumerate() {
+            for (x, char) in line.chars().enumerate() {
+                if predicate(char) {
+                    grid.insert((y as i32, x as i32));
+                }
+            }
+        }
+        grid
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn bounds(&self) -> Option<Bounds> {
+        if self.height == 0 || self.width == 0 {
+            return None;
+        }
+        Some(Bounds {
+            min: (0, 0),
+            max: (self.height as i32 - 1, self.width as i32 - 1),
+        })
+    }
+
+    pub fn in_bounds(&self, pos: (i32, i32)) -> bool {
+        pos.0 >= 0 && pos.1 >= 0 && (pos.0 as usize) < self.height && (pos.1 as usize) < self.width
+    }
+
+    fn bit(&self, pos: (i32, i32)) -> Option<(usize, u64)> {
+        self.in_bounds(pos).then(|| {
+            let index = pos.0 as usize * self.width + pos.1 as usize;
+            (index / WORD_BITS, 1 << (index % WORD_BITS))
+        })
+    }
+
+    /// Whether `pos` is set. Out of bounds cells are never set.
+    pub fn contains(&self, pos: (i32, i32)) -> bool {
+        self.bit(pos)
+            .is_some_and(|(word, mask)| self.words[word] & mask != 0)
+    }
+
+    /// Sets `pos`, returning true if it was in bounds and not already set.
+    pub fn insert(&mut self, pos: (i32, i32)) -> bool {
+        let Some((word, mask)) = self.bit(pos) else {
+            return false;
+        };
+        let was_set = self.words[word] & mask != 0;
+        self.words[word] |= mask;
+        !was_set
+    }
+
+    /// Clears `pos`, returning true if it was set.
+    pub fn remove(&mut self, pos: (i32, i32)) -> bool {
+        let Some((word, mask)) = self.bit(pos) else {
+            return false;
+        };
+        let was_set = self.words[word] & mask != 0;
+        self.words[word] &= !mask;
+        was_set
+    }
+
+    /// Stores `value` at `pos`, returning false if `pos` is outside the grid.
+    pub fn set(&mut self, pos: (i32, i32), value: bool) -> bool {
+        if !self.in_bounds(pos) {
+            return false;
+        }
+        if value {
+            self.insert(pos);
+        } else {
+            self.remove(pos);
+        }
+        true
+    }
+
+    /// Number of set cells.
+    pub fn count(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    fn assert_same_size(&self, other: &Self) {
+        assert_eq!(
+            (self.height, self.width),
+            (other.height, other.width),
+            "bit grids must have the same size"
+        );
+    }
+
+    pub fn union_with(&mut self, other: &Self) {
+        self.assert_same_size(other);
+        self.words
+            .iter_mut()
+            .zip(&other.words)
+            .for_each(|(a, b)| *a |= b);
+    }
+
+    pub fn intersect_with(&mut self, other: &Self) {
+        self.assert_same_size(other);
+        self.words
+            .iter_mut()
+            .zip(&other.words)
+            .for_each(|(a, b)| *a &= b);
+    }
+
+    pub fn difference_with(&mut self, other: &Self) {
+        self.assert_same_size(other);
+        self.words
+            .iter_mut()
+            .zip(&other.words)
+            .for_each(|(a, b)| *a &= !b);
+    }
+
+    /// Positions of the set cells in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let width = self.width;
+        self.words
+            .iter()
+            .enumerate()
+            .flat_map(|(word_index, word)| {
+                let mut word = *word;
+                std::iter::from_fn(move || {
+                    if word == 0 {
+                        return None;
+                    }
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    Some(word_index * WORD_BITS + bit)
+                })
+            })
+            .map(move |index| ((index / width) as i32, (index % width) as i32))
+    }
+}
+
+impl fmt::Display for BitGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                write!(f, "{}", if self.contains((y, x)) { '#' } else { '.' })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitGrid;
+
+    #[test]
+    fn insert_and_count() {
+        let mut grid = BitGrid::new(3, 30);
+        assert!(grid.is_empty());
+        assert!(grid.insert((0, 0)));
+        assert!(!grid.insert((0, 0)));
+        assert!(grid.insert((2, 29)));
+        assert!(!grid.insert((3, 0)));
+        assert!(!grid.insert((-1, 0)));
+        assert!(grid.contains((2, 29)));
+        assert!(!grid.contains((1, 1)));
+        assert_eq!(2, grid.count());
+        assert_eq!(vec![(0, 0), (2, 29)], grid.iter().collect::<Vec<_>>());
+
+        assert!(grid.remove((0, 0)));
+        assert!(!grid.remove((0, 0)));
+        assert_eq!(1, grid.count());
+    }
+
+    #[test]
+    fn set_operations() {
+        let a_input = "##.\n...";
+        let b_input = ".##\n..#";
+        let a = BitGrid::parse(a_input, |char| char == '#');
+        let b = BitGrid::parse(b_input, |char| char == '#');
+
+        let mut union = a.clone();
+        union.union_with(&b);
+        assert_eq!("###\n..#\n", union.to_string());
+
+        let mut intersection = a.clone();
+        intersection.intersect_with(&b);
+        assert_eq!(vec![(0, 1)], intersection.iter().collect::<Vec<_>>());
+
+        let mut difference = a;
+        difference.difference_with(&b);
+        assert_eq!(vec![(0, 0)], difference.iter().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,196 @@
+//! Connected-component labelling over rectangular grids.
+
+use crate::grid::{Bounds, Grid, DIRECTIONS_4};
+
+/// Disjoint-set forest with path halving and union by size.
+#[derive(Clone, Debug)]
+pub struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    pub fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+        }
+    }
+
+    pub fn find(&mut self, mut item: usize) -> usize {
+        while self.parent[item] != item {
+            self.parent[item] = self.parent[self.parent[item]];
+            item = self.parent[item];
+        }
+        item
+    }
+
+    /// Merges the sets of `a` and `b`, returning false if they were already joined.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        true
+    }
+
+    pub fn set_size(&mut self, item: usize) -> usize {
+        let root = self.find(item);
+        self.size[root]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+impl Connectivity {
+    /// Neighbours already visited by a row-major scan.
+    fn preceding(&self) -> &'static [(i32, i32)] {
+        match self {
+            Connectivity::Four => &[(-1, 0), (0, -1)],
+            Connectivity::Eight => &[(-1, -1), (-1, 0), (-1, 1), (0, -1)],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Component {
+    pub label: usize,
+    pub area: usize,
+    /// Number of cell edges bordering another component or the grid edge.
+    pub perimeter: usize,
+    pub bounds: Bounds,
+    /// Cells in row-major order.
+    pub cells: Vec<(i32, i32)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Components {
+    /// Component label of every cell, indexing into `components`.
+    pub labels: Grid<usize>,
+    pub components: Vec<Component>,
+}
+
+/// Labels the connected regions of `grid`. Two neighbouring cells belong to the
+/// same region when `same_region` holds for their values. Labels are assigned
+/// in the order regions are first met in a row-major scan.
+pub fn label_components<T>(
+    grid: &Grid<T>,
+    connectivity: Connectivity,
+    mut same_region: impl FnMut(&T, &T) -> bool,
+) -> Components {
+    let width = grid.width();
+    let index = |pos: (i32, i32)| pos.0 as usize * width + pos.1 as usize;
+
+    let mut sets = UnionFind::new(grid.height() * width);
+    for (pos, value) in grid.iter() {
+        for dir in connectivity.preceding() {
+            let other = (pos.0 + dir.0, pos.1 + dir.1);
+            if let Some(other_value) = grid.get(other) {
+                if same_region(value, other_value) {
+                    sets.union(index(pos), index(other));
+                }
+            }
+        }
+    }
+
+    let mut root_labels = vec![usize::MAX; grid.height() * width];
+    let mut components: Vec<Component> = Vec::new();
+    let mut labels = Grid::new(grid.height(), width, 0);
+    for pos in grid.positions() {
+        let root = sets.find(index(pos));
+        if root_labels[root] == usize::MAX {
+            root_labels[root] = components.len();
+            components.push(Component {
+                label: components.len(),
+                area: 0,
+                perimeter: 0,
+                bounds: Bounds { min: pos, max: pos },
+                cells: Vec::new(),
+            });
+        }
+        let component = &mut components[root_labels[root]];
+        component.area += 1;
+        component.bounds.min = (component.bounds.min.0, component.bounds.min.1.min(pos.1));
+        component.bounds.max = (pos.0, component.bounds.max.1.max(pos.1));
+        component.cells.push(pos);
+        labels[pos] = component.label;
+    }
+
+    for (pos, label) in labels.iter() {
+        let open_sides = DIRECTIONS_4
+            .iter()
+            .filter(|dir| labels.get((pos.0 + dir.0, pos.1 + dir.1)) != Some(label))
+            .count();
+        components[*label].perimeter += open_sides;
+    }
+
+    Components { labels, components }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{label_components, Connectivity, UnionFind};
+    use crate::grid::{Bounds, Grid};
+
+    #[test]
+    fn union_find() {
+        let mut sets = UnionFind::new(5);
+        assert!(sets.union(0, 1));
+        assert!(sets.union(3, 4));
+        assert!(!sets.union(1, 0));
+        assert!(sets.union(1, 4));
+        assert_eq!(sets.find(0), sets.find(3));
+        assert_eq!(4, sets.set_size(4));
+        assert_eq!(1, sets.set_size(2));
+    }
+
+    #[test]
+    fn garden_regions() {
+        let grid = Grid::parse("AAAA\nBBCD\nBBCC\nEEEC", |char| char);
+        let regions = label_components(&grid, Connectivity::Four, |a, b| a == b);
+
+        let summary: Vec<_> = regions
+            .components
+            .iter()
+            .map(|region| (grid[region.cells[0]], region.area, region.perimeter))
+            .collect();
+        assert_eq!(
+            vec![
+                ('A', 4, 10),
+                ('B', 4, 8),
+                ('C', 4, 10),
+                ('D', 1, 4),
+                ('E', 3, 8)
+            ],
+            summary
+        );
+        assert_eq!(
+            Bounds {
+                min: (1, 2),
+                max: (3, 3)
+            },
+            regions.components[2].bounds
+        );
+        assert_eq!(2, regions.labels[(3, 3)]);
+    }
+
+    #[test]
+    fn diagonal_connectivity() {
+        let grid = Grid::parse("#.\n.#", |char| char == '#');
+        let four = label_components(&grid, Connectivity::Four, |a, b| *a && *b);
+        assert_eq!(4, four.components.len());
+        let eight = label_components(&grid, Connectivity::Eight, |a, b| *a && *b);
+        assert_eq!(3, eight.components.len());
+        assert_eq!(vec![(0, 0), (1, 1)], eight.components[0].cells);
+        assert_eq!(8, eight.components[0].perimeter);
+    }
+}
@@ -0,0 +1,161 @@
+//! Cycle detection for step-by-step simulations.
+//!
+//! A simulation is an initial state and a step function. Once a state repeats,
+//! every later state repeats with it, so the sequence is `start` distinct
+//! states followed by a loop of `length` states. Simulations that may stop on
+//! their own are walked with [`crate::search::walk_loops`] instead.
+
+use std::{collections::HashMap, hash::Hash};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cycle {
+    /// Index of the first state that is part of the loop.
+    pub start: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    /// Index within the first lap of the loop with the same state as step `n`.
+    pub fn equivalent_step(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.length
+        }
+    }
+}
+
+/// Floyd's tortoise and hare. Keeps two states in memory; the step function
+/// must never end.
+pub fn floyd<S: PartialEq>(initial: S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    let mut tortoise = step(&initial);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        let halfway = step(&hare);
+        hare = step(&halfway);
+    }
+
+    let mut start = 0;
+    let mut tortoise = initial;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+
+    let mut length = 1;
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        length += 1;
+    }
+    Cycle { start, length }
+}
+
+/// Brent's algorithm. Same result as [`floyd`] with fewer calls to `step`.
+pub fn brent<S: PartialEq + Clone>(initial: S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare);
+        length += 1;
+    }
+
+    let mut tortoise = initial.clone();
+    let mut hare = initial;
+    for _ in 0..length {
+        hare = step(&hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+    Cycle { start, length }
+}
+
+/// State after `n` steps, skipping whole laps once the simulation loops.
+pub fn fast_forward<S: Hash + Eq + Clone>(
+    initial: S,
+    mut step: impl FnMut(&S) -> S,
+    n: usize,
+) -> S {
+    let mut history: Vec<S> = Vec::new();
+    let mut seen: HashMap<S, usize> = HashMap::new();
+    let mut state = initial;
+    for index in 0..n {
+        if let Some(start) = seen.insert(state.clone(), index) {
+            let cycle = Cycle {
+                start,
+                length: index - start,
+            };
+            return history.swap_remove(cycle.equivalent_step(n));
+        }
+        let next = step(&state);
+        history.push(state);
+        state = next;
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{brent, fast_forward, floyd, Cycle};
+
+    // 0 -> 1 -> 2 -> 3 -> 4 -> 5 -> 2 ...
+    fn lasso(n: &u32) -> u32 {
+        if *n == 5 {
+            2
+        } else {
+            n + 1
+        }
+    }
+
+    #[test]
+    fn all_detectors_agree() {
+        let expected = Cycle {
+            start: 2,
+            length: 4,
+        };
+        assert_eq!(expected, floyd(0, lasso));
+        assert_eq!(expected, brent(0, lasso));
+
+        // pure loop from the first state
+        let ring = |n: &u64| (n * 3) % 7;
+        let expected = Cycle {
+            start: 0,
+            length: 6,
+        };
+        assert_eq!(expected, floyd(1, ring));
+        assert_eq!(expected, brent(1, ring));
+    }
+
+    #[test]
+    fn fast_forwarding() {
+        for n in 0..20 {
+            let mut slow = 0;
+            for _ in 0..n {
+                slow = lasso(&slow);
+            }
+            assert_eq!(slow, fast_forward(0, lasso, n));
+        }
+        assert_eq!(3, fast_forward(0, lasso, 1_000_000_000_003));
+        assert_eq!(
+            4,
+            Cycle {
+                start: 3,
+                length: 5
+            }
+            .equivalent_step(14)
+        );
+    }
+}
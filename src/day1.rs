@@ -1,51 +1,493 @@
 use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, VecDeque},
     env,
-    io::{self, BufRead},
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, Write},
 };
 
-use advent_of_code_2024::{get_input, task_argument};
+use advent_of_code_2024::{parse::signed_integers, task_argument};
 
 fn main() {
-    task_argument(part1, part2);
+    match env::args().nth(1).as_deref() {
+        Some("external") => external(env::args().skip(2).collect()),
+        _ => task_argument(part1, part2),
+    }
 }
 
-fn input_to_integer_vecs(input_lines: Vec<String>) -> (Vec<i32>, Vec<i32>) {
-    input_lines
-        .iter()
-        .map(|line| {
-            let mut elements = line.split_ascii_whitespace();
-            let left = elements.next().unwrap().parse::<i32>().unwrap();
-            let right = elements.next().unwrap().parse::<i32>().unwrap();
-            (left, right)
-        })
-        .unzip()
+/// Reads the two columns line by line, without keeping the lines around.
+fn read_columns(mut reader: impl BufRead) -> (Vec<i64>, Vec<i64>) {
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line).expect("Failed to read line") != 0 {
+        if let Some((a, b)) = column_pair(&line).unwrap_or_else(|err| panic!("{}", err)) {
+            left.push(a);
+            right.push(b);
+        }
+        line.clear();
+    }
+    (left, right)
 }
 
-fn part1() {
-    let input_lines = get_input();
-    let (mut left, mut right) = input_to_integer_vecs(input_lines);
+/// The two numbers on `line`, or `None` for a blank line.
+fn column_pair(line: &str) -> io::Result<Option<(i64, i64)>> {
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+    match signed_integers::<i64>(line)[..] {
+        [a, b] => Ok(Some((a, b))),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Expected two numbers: {:?}", line.trim_end()),
+        )),
+    }
+}
 
-    left.sort();
-    right.sort();
+fn distance(left: &mut [i64], right: &mut [i64]) -> i64 {
+    left.sort_unstable();
+    right.sort_unstable();
+    left.iter()
+        .zip(right.iter())
+        .map(|(a, b)| (a - b).abs())
+        .sum()
+}
 
-    let res = left
-        .into_iter()
-        .zip(right)
-        .fold(0, |acc, tuple| acc + (tuple.0 - tuple.1).abs());
+fn similarity(left: &[i64], right: &[i64]) -> i64 {
+    let mut counts: HashMap<i64, i64> = HashMap::new();
+    for number in right {
+        *counts.entry(*number).or_default() += 1;
+    }
+    left.iter()
+        .map(|number| number * counts.get(number).unwrap_or(&0))
+        .sum()
+}
+
+fn part1() {
+    let (mut left, mut right) = read_columns(io::stdin().lock());
+    let res = distance(&mut left, &mut right);
     println!("res: {}", res)
 }
 
 fn part2() {
-    let input_lines = get_input();
-    let (left, right) = input_to_integer_vecs(input_lines);
-
-    let similarity_score = left.iter().fold(0, |total, elem| {
-        total
-            + elem
-                * right
-                    .iter()
-                    .filter(|right_elem| elem == *right_elem)
-                    .count() as i32
-    });
+    let (left, right) = read_columns(io::stdin().lock());
+    let similarity_score = similarity(&left, &right);
     println!("similarity_score: {}", similarity_score)
 }
+
+/// Sorted runs merged at once unless `--fan-in` says otherwise.
+const FAN_IN: usize = 64;
+
+/// `day1 external [--chunk NUMBERS] [--fan-in RUNS] [FILE]`, reading stdin
+/// without a file
+fn external(args: Vec<String>) {
+    let mut chunk = 1 << 24;
+    let mut fan_in = FAN_IN;
+    let mut file = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--chunk" => {
+                chunk = args
+                    .next()
+                    .and_then(|chunk| chunk.parse().ok())
+                    .filter(|chunk| *chunk > 0)
+                    .expect("--chunk needs a positive number")
+            }
+            "--fan-in" => {
+                fan_in = args
+                    .next()
+                    .and_then(|fan_in| fan_in.parse().ok())
+                    .filter(|fan_in| *fan_in > 1)
+                    .expect("--fan-in needs a number above 1")
+            }
+            _ => file = Some(arg),
+        }
+    }
+
+    let scores = match file {
+        Some(file) => external_scores(BufReader::new(File::open(file).unwrap()), chunk, fan_in),
+        None => external_scores(io::stdin().lock(), chunk, fan_in),
+    };
+    let (distance, similarity) = scores.expect("External sort failed");
+    println!("res: {}", distance);
+    println!("similarity_score: {}", similarity);
+}
+
+/// Distance and similarity score with at most `chunk` numbers per column in
+/// memory. Each column is cut into sorted runs on temporary files, which are
+/// merged `fan_in` at a time until one merge covers them all. Both scores
+/// then come from a single pass over the merged columns.
+fn external_scores(
+    mut reader: impl BufRead,
+    chunk: usize,
+    fan_in: usize,
+) -> io::Result<(i64, i64)> {
+    if chunk == 0 || fan_in < 2 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "chunk must be positive and fan-in at least 2",
+        ));
+    }
+
+    let mut left_runs = Vec::new();
+    let mut right_runs = Vec::new();
+    let mut left = Vec::with_capacity(chunk);
+    let mut right = Vec::with_capacity(chunk);
+    let mut line = String::new();
+    loop {
+        let read = reader.read_line(&mut line)?;
+        if let Some((a, b)) = column_pair(&line)? {
+            left.push(a);
+            right.push(b);
+        }
+        line.clear();
+
+        if left.len() == chunk || (read == 0 && !left.is_empty()) {
+            left.sort_unstable();
+            right.sort_unstable();
+            left_runs.push(write_run(left.drain(..).map(Ok))?);
+            right_runs.push(write_run(right.drain(..).map(Ok))?);
+        }
+        if read == 0 {
+            break;
+        }
+    }
+    let left_runs = reduce_runs(left_runs, fan_in)?;
+    let right_runs = reduce_runs(right_runs, fan_in)?;
+
+    merge_scores(Merge::new(&left_runs)?, Merge::new(&right_runs)?, chunk)
+}
+
+/// Distance and similarity of two sorted columns in one pass that reads each
+/// number once. Both columns are stepped together for the distance, and each
+/// is cut into runs of equal numbers as it goes by. Runs are scored once both
+/// sides have finished them, and dropped once the other side has passed them.
+/// The side that is ahead keeps at most `limit` of its runs in memory.
+fn merge_scores(
+    left: impl Iterator<Item = io::Result<i64>>,
+    right: impl Iterator<Item = io::Result<i64>>,
+    limit: usize,
+) -> io::Result<(i64, i64)> {
+    let (mut left, mut right) = (left.fuse(), right.fuse());
+    let mut left_runs = EqualRuns::new(limit);
+    let mut right_runs = EqualRuns::new(limit);
+    let mut distance = 0;
+    let mut similarity = 0;
+    loop {
+        match (left.next().transpose()?, right.next().transpose()?) {
+            (Some(a), Some(b)) => {
+                distance += (a - b).abs();
+                left_runs.push(a)?;
+                right_runs.push(b)?;
+            }
+            (None, None) => break,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Columns have different lengths",
+                ))
+            }
+        }
+        similarity += join_runs(&mut left_runs, &mut right_runs)?;
+    }
+    left_runs.end()?;
+    right_runs.end()?;
+    Ok((
+        distance,
+        similarity + join_runs(&mut left_runs, &mut right_runs)?,
+    ))
+}
+
+/// Scores the runs both sides have finished and drops the ones the other side
+/// has passed. Stops at an equal run that is still growing on either side.
+fn join_runs(left: &mut EqualRuns, right: &mut EqualRuns) -> io::Result<i64> {
+    let mut similarity = 0;
+    while let (Some((a, count_a, open_a)), Some((b, count_b, open_b))) =
+        (left.front()?, right.front()?)
+    {
+        match a.cmp(&b) {
+            Ordering::Less => left.pop_front()?,
+            Ordering::Greater => right.pop_front()?,
+            Ordering::Equal if open_a || open_b => break,
+            Ordering::Equal => {
+                similarity += a * count_a * count_b;
+                left.pop_front()?;
+                right.pop_front()?;
+            }
+        }
+    }
+    Ok(similarity)
+}
+
+/// Runs of equal numbers from one sorted column, as `(number, count)`, oldest
+/// first. Finished runs beyond `limit` at either end of the queue wait on
+/// temporary files of `limit` runs each.
+struct EqualRuns {
+    head: VecDeque<(i64, i64)>,
+    spilled: VecDeque<File>,
+    tail: Vec<(i64, i64)>,
+    /// The run the next number may still extend.
+    open: Option<(i64, i64)>,
+    limit: usize,
+}
+
+impl EqualRuns {
+    fn new(limit: usize) -> Self {
+        Self {
+            head: VecDeque::new(),
+            spilled: VecDeque::new(),
+            tail: Vec::new(),
+            open: None,
+            limit: limit.max(1),
+        }
+    }
+
+    fn push(&mut self, number: i64) -> io::Result<()> {
+        if let Some((last, count)) = &mut self.open {
+            if *last == number {
+                *count += 1;
+                return Ok(());
+            }
+        }
+        match self.open.replace((number, 1)) {
+            Some(run) => self.finish(run),
+            None => Ok(()),
+        }
+    }
+
+    /// Finishes the open run, as the column has no more numbers.
+    fn end(&mut self) -> io::Result<()> {
+        match self.open.take() {
+            Some(run) => self.finish(run),
+            None => Ok(()),
+        }
+    }
+
+    fn finish(&mut self, run: (i64, i64)) -> io::Result<()> {
+        if self.spilled.is_empty() && self.tail.is_empty() && self.head.len() < self.limit {
+            self.head.push_back(run);
+        } else {
+            self.tail.push(run);
+            if self.tail.len() == self.limit {
+                let mut writer = BufWriter::new(tempfile::tempfile()?);
+                for (number, count) in self.tail.drain(..) {
+                    writer.write_all(&number.to_le_bytes())?;
+                    writer.write_all(&count.to_le_bytes())?;
+                }
+                let mut file = writer.into_inner()?;
+                file.rewind()?;
+                self.spilled.push_back(file);
+            }
+        }
+        Ok(())
+    }
+
+    /// The oldest run, and whether it is still open.
+    fn front(&mut self) -> io::Result<Option<(i64, i64, bool)>> {
+        if self.head.is_empty() {
+            match self.spilled.pop_front() {
+                Some(file) => {
+                    let mut reader = BufReader::new(file);
+                    let mut bytes = [0; 8];
+                    for _ in 0..self.limit {
+                        reader.read_exact(&mut bytes)?;
+                        let number = i64::from_le_bytes(bytes);
+                        reader.read_exact(&mut bytes)?;
+                        self.head.push_back((number, i64::from_le_bytes(bytes)));
+                    }
+                }
+                None => self.head.extend(self.tail.drain(..)),
+            }
+        }
+        Ok(match self.head.front() {
+            Some(&(number, count)) => Some((number, count, false)),
+            None => self.open.map(|(number, count)| (number, count, true)),
+        })
+    }
+
+    fn pop_front(&mut self) -> io::Result<()> {
+        self.front()?;
+        if self.head.pop_front().is_none() {
+            self.open = None;
+        }
+        Ok(())
+    }
+}
+
+/// Merges runs `fan_in` at a time into longer runs until at most `fan_in`
+/// are left, so no merge opens more files than that.
+fn reduce_runs(mut runs: Vec<File>, fan_in: usize) -> io::Result<Vec<File>> {
+    while runs.len() > fan_in {
+        runs = runs
+            .chunks(fan_in)
+            .map(|group| write_run(Merge::new(group)?))
+            .collect::<io::Result<_>>()?;
+    }
+    Ok(runs)
+}
+
+/// Writes sorted `numbers` to a new temporary file, rewound for reading.
+fn write_run(numbers: impl IntoIterator<Item = io::Result<i64>>) -> io::Result<File> {
+    let mut writer = BufWriter::new(tempfile::tempfile()?);
+    for number in numbers {
+        writer.write_all(&number?.to_le_bytes())?;
+    }
+    let mut file = writer.into_inner()?;
+    file.rewind()?;
+    Ok(file)
+}
+
+/// Sorted runs merged into one sorted stream.
+struct Merge {
+    runs: Vec<BufReader<File>>,
+    heads: BinaryHeap<Reverse<(i64, usize)>>,
+}
+
+impl Merge {
+    /// Reads every run from its start, leaving the files to be merged again.
+    fn new(runs: &[File]) -> io::Result<Self> {
+        let mut readers = Vec::with_capacity(runs.len());
+        for run in runs {
+            let mut file = run.try_clone()?;
+            file.rewind()?;
+            readers.push(BufReader::new(file));
+        }
+        let mut merge = Self {
+            runs: readers,
+            heads: BinaryHeap::new(),
+        };
+        for run in 0..merge.runs.len() {
+            merge.advance(run)?;
+        }
+        Ok(merge)
+    }
+
+    fn advance(&mut self, run: usize) -> io::Result<()> {
+        let mut bytes = [0; 8];
+        match self.runs[run].read_exact(&mut bytes) {
+            Ok(()) => self.heads.push(Reverse((i64::from_le_bytes(bytes), run))),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => (),
+            Err(err) => return Err(err),
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for Merge {
+    type Item = io::Result<i64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((number, run)) = self.heads.pop()?;
+        Some(self.advance(run).map(|_| number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, io};
+
+    use crate::{column_pair, distance, external_scores, merge_scores, read_columns, similarity};
+
+    const INPUT: &str = "3   4
+4   3
+2   5
+1   3
+3   9
+3   3
+";
+
+    #[test]
+    fn test_part1() {
+        let (mut left, mut right) = read_columns(INPUT.as_bytes());
+        assert_eq!(11, distance(&mut left, &mut right));
+    }
+
+    #[test]
+    fn test_part2() {
+        let (left, right) = read_columns(INPUT.as_bytes());
+        assert_eq!(31, similarity(&left, &right));
+    }
+
+    #[test]
+    fn large_values() {
+        let input = "3000000000   1\n-3000000000   3000000000\n";
+        let (mut left, mut right) = read_columns(input.as_bytes());
+        assert_eq!(3000000000, similarity(&left, &right));
+        assert_eq!(3000000001, distance(&mut left, &mut right));
+    }
+
+    #[test]
+    fn malformed_lines() {
+        assert_eq!(Some((3, -4)), column_pair("3   -4\n").unwrap());
+        assert_eq!(None, column_pair("  \n").unwrap());
+        assert!(column_pair("3\n").is_err());
+        assert!(column_pair("3 4 5\n").is_err());
+        let (left, _) = read_columns("1   2\n\n3   4\n".as_bytes());
+        assert_eq!(vec![1, 3], left);
+    }
+
+    #[test]
+    fn external_sort() {
+        assert_eq!((11, 31), external_scores(INPUT.as_bytes(), 4, 2).unwrap());
+        assert_eq!((0, 0), external_scores("".as_bytes(), 4, 2).unwrap());
+        assert!(external_scores(INPUT.as_bytes(), 0, 2).is_err());
+        assert!(external_scores("1 2\n3\n".as_bytes(), 4, 2).is_err());
+
+        // pseudo-random columns over a small range, so values repeat across runs
+        let mut seed = 7_u64;
+        let input: String = (0..5000)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                let a = (seed >> 33) % 300;
+                let b = (seed >> 13) % 200 + 50;
+                format!("{}   {}\n", a, b)
+            })
+            .collect();
+        let (mut left, mut right) = read_columns(input.as_bytes());
+        let expected = (similarity(&left, &right), distance(&mut left, &mut right));
+        for (chunk, fan_in) in [(50, 2), (50, 7), (1000, 64), (10000, 2)] {
+            let (distance, similarity) = external_scores(input.as_bytes(), chunk, fan_in).unwrap();
+            assert_eq!(expected, (similarity, distance));
+        }
+    }
+
+    #[test]
+    fn single_pass() {
+        // left stays on 0 while right runs ahead through distinct values, which
+        // wait on spill files until the left catches up
+        let left: Vec<i64> = [0; 500].into_iter().chain(1..=500).collect();
+        let right: Vec<i64> = (0..1000).collect();
+        let (mut sorted_left, mut sorted_right) = (left.clone(), right.clone());
+        let expected = (
+            distance(&mut sorted_left, &mut sorted_right),
+            similarity(&left, &right),
+        );
+
+        let reads = Cell::new(0);
+        let counted = |numbers: &Vec<i64>| {
+            let reads = &reads;
+            numbers.clone().into_iter().map(move |number| {
+                reads.set(reads.get() + 1);
+                Ok(number)
+            })
+        };
+        for limit in [1, 2, 7, 2000] {
+            reads.set(0);
+            let scores = merge_scores(counted(&left), counted(&right), limit).unwrap();
+            assert_eq!(expected, scores);
+            assert_eq!(2000, reads.get());
+        }
+
+        let numbers = |numbers: Vec<i64>| numbers.into_iter().map(Ok);
+        let left = numbers(vec![1, 2, 2, 3, 3, 3]);
+        let right = numbers(vec![2, 3, 3, 4, 4, 4]);
+        assert_eq!(
+            (6, 2 * 2 + 3 * 3 * 2),
+            merge_scores(left, right, 1).unwrap()
+        );
+
+        let failing = vec![Ok(1), Err(io::Error::other("broken"))].into_iter();
+        assert!(merge_scores(failing, numbers(vec![1, 2]), 4).is_err());
+    }
+}
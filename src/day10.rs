@@ -1,7 +1,10 @@
 use std::time::Instant;
 
-use advent_of_code_2024::task_argument_with_input;
-use nom::InputIter;
+use advent_of_code_2024::{
+    grid::{Grid, DIRECTIONS_4},
+    pathfinding::bfs,
+    task_argument_with_input,
+};
 
 fn main() {
     let time = Instant::now();
@@ -9,33 +12,19 @@ fn main() {
     println!("Res: {}, Took: {:?}", res, time.elapsed());
 }
 
-fn parse_input(input: String) -> Vec<Vec<u32>> {
-    input
-        .lines()
-        .map(|line| {
-            line.iter_elements()
-                .map(|e| e.to_digit(10).unwrap())
-                .collect()
-        })
-        .collect()
+fn parse_input(input: String) -> Grid<u32> {
+    Grid::parse(&input, |e| e.to_digit(10).unwrap())
 }
 
 fn task1(input: String) -> String {
     let map = parse_input(input);
 
-    let count: u32 = map
-        .iter()
-        .enumerate()
-        .map(|(y, line)| {
-            line.iter()
-                .enumerate()
-                .map(|(x, _)| {
-                    let mut vec = check(&map, 0, y as i32, x as i32);
-                    vec.sort();
-                    vec.dedup();
-                    vec.len() as u32
-                })
-                .sum::<u32>()
+    let count: usize = trailheads(&map)
+        .map(|start| {
+            bfs(&map, [start], |from, to| map[to] == map[from] + 1)
+                .reachable()
+                .filter(|(pos, _)| map[*pos] == 9)
+                .count()
         })
         .sum();
 
@@ -45,42 +34,28 @@ fn task1(input: String) -> String {
 fn task2(input: String) -> String {
     let map = parse_input(input);
 
-    let count: u32 = map
-        .iter()
-        .enumerate()
-        .map(|(y, line)| {
-            line.iter()
-                .enumerate()
-                .map(|(x, _)| check(&map, 0, y as i32, x as i32).len() as u32)
-                .sum::<u32>()
-        })
-        .sum();
-
-    count.to_string()
-}
-
-fn check(map: &[Vec<u32>], level: u32, y: i32, x: i32) -> Vec<(i32, i32)> {
-    if out_of_bounds(map, y, x) {
-        return Vec::new();
-    }
-    if map[y as usize][x as usize] != level || level > 9 {
-        return Vec::new();
+    // number of distinct trails from each cell up to a 9, filled from the top down
+    let mut trails = map.map(|level| u64::from(*level == 9));
+    for level in (0..9).rev() {
+        for (pos, _) in map.iter().filter(|(_, height)| **height == level) {
+            trails[pos] = map
+                .neighbours(pos, &DIRECTIONS_4)
+                .filter(|next| map[*next] == level + 1)
+                .map(|next| trails[next])
+                .sum();
+        }
     }
-    if level == 9 {
-        return vec![(y, x)];
-    };
 
-    DIRECTIONS
-        .iter()
-        .flat_map(|(y_offset, x_offset)| check(map, level + 1, y + y_offset, x + x_offset))
-        .collect()
-}
+    let count: u64 = trailheads(&map).map(|start| trails[start]).sum();
 
-fn out_of_bounds(map: &[Vec<u32>], y: i32, x: i32) -> bool {
-    y >= map.len() as i32 || y < 0 || x < 0 || x >= map.first().unwrap().len() as i32
+    count.to_string()
 }
 
-const DIRECTIONS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+fn trailheads(map: &Grid<u32>) -> impl Iterator<Item = (i32, i32)> + '_ {
+    map.iter()
+        .filter(|(_, height)| **height == 0)
+        .map(|(pos, _)| pos)
+}
 
 #[cfg(test)]
 mod tests {
@@ -1,7 +1,6 @@
 use std::{collections::HashMap, time::Instant};
 
-use advent_of_code_2024::task_argument_with_input;
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use advent_of_code_2024::{math::split_half, parse::unsigned_integers, task_argument_with_input};
 
 fn main() {
     let time = Instant::now();
@@ -10,17 +9,14 @@ fn main() {
 }
 
 fn parse_input(input: String) -> Vec<u64> {
-    input
-        .split_whitespace()
-        .map(|val| val.parse::<u64>().unwrap())
-        .collect()
+    unsigned_integers(&input)
 }
 
 fn task1(input: String) -> String {
     let mut input = parse_input(input);
     println!("got input: {:?}", input);
 
-    for x in 0..25 {
+    for _ in 0..25 {
         input = solve1(input);
     }
 
@@ -29,28 +25,17 @@ fn task1(input: String) -> String {
 
 //bruteforce
 fn solve1(input: Vec<u64>) -> Vec<u64> {
-    input
-        .iter()
-        .flat_map(|val| match val {
-            0 => vec![1],
-            x if (x.ilog10() + 1) % 2 == 0 => {
-                let (left, right) = split_number(*x);
-                vec![left, right]
-            }
-            _ => vec![val * 2024],
-        })
-        .collect()
+    input.iter().flat_map(|val| blink(*val)).collect()
 }
 
-fn split_number(num: u64) -> (u64, u64) {
-    let digits = num.ilog10() + 1;
-    let mid = digits / 2;
-    let divisor = 10_u64.pow(mid);
-
-    let right = num % divisor;
-    let left = num / divisor;
-
-    (left, right)
+fn blink(stone: u64) -> Vec<u64> {
+    if stone == 0 {
+        return vec![1];
+    }
+    match split_half(stone) {
+        Some((left, right)) => vec![left, right],
+        None => vec![stone * 2024],
+    }
 }
 
 fn task2(input: String) -> String {
@@ -66,16 +51,7 @@ fn task2(input: String) -> String {
     for _ in 0..75 {
         let mut new_counter: HashMap<u64, usize> = HashMap::with_capacity(counter.capacity());
         counter.iter().for_each(|(val, og_count)| {
-            let stones = match val {
-                0 => vec![1],
-                x if (x.ilog10() + 1) % 2 == 0 => {
-                    let (left, right) = split_number(*x);
-                    vec![left, right]
-                }
-                val => vec![val * 2024],
-            };
-
-            for stone in stones {
+            for stone in blink(*val) {
                 *new_counter.entry(stone).or_insert(0) += og_count;
             }
         });
@@ -101,7 +77,7 @@ mod tests {
     fn test2() {
         let mut input = parse_input("125 17".to_string());
 
-        for x in 0..6 {
+        for _ in 0..6 {
             input = solve1(input);
         }
 
@@ -115,7 +91,7 @@ mod tests {
             &res
         );
 
-        for x in 6..25 {
+        for _ in 6..25 {
             input = solve1(input);
         }
 
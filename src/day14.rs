@@ -1,29 +1,32 @@
-use std::{
-    io::Read,
-    str::FromStr,
-    thread,
-    time::{Duration, Instant},
+use std::{env, str::FromStr, time::Instant};
+
+use advent_of_code_2024::{
+    grid::Grid,
+    math::crt,
+    parse::key_pair,
+    simulation::{Driver, Simulation},
+    task_argument_with_input,
 };
 
-use advent_of_code_2024::task_argument_with_input;
-
 fn main() {
-    let time = Instant::now();
-    let res = task_argument_with_input("inputs/14.txt", task1, task2);
-    println!("Res: {}, Took: {:?}", res, time.elapsed());
+    match env::args().nth(1).as_deref() {
+        Some("show") => show_tree(env::args().nth(2).as_deref().unwrap_or("inputs/14.txt")),
+        _ => {
+            let time = Instant::now();
+            let res = task_argument_with_input("inputs/14.txt", task1, task2);
+            println!("Res: {}, Took: {:?}", res, time.elapsed());
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Vec2(i32, i32);
 
 impl FromStr for Vec2 {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let pos: String = s.chars().skip(2).collect();
-        let mut pos = pos.split(",");
-        let x = pos.next().unwrap().parse::<i32>().unwrap();
-        let y = pos.next().unwrap().parse::<i32>().unwrap();
+        let (_, x, y) = key_pair(s).map_err(|_| ())?;
         Ok(Self(x, y))
     }
 }
@@ -42,18 +45,52 @@ fn parse_input(input: String) -> (Vec<Vec2>, Vec<Vec2>) {
         .unzip()
 }
 
+/// Every robot moving by its velocity once per second, wrapping around the edges.
+struct Robots {
+    pos: Vec<Vec2>,
+    vels: Vec<Vec2>,
+    width: i32,
+    height: i32,
+}
+
+impl Simulation for Robots {
+    type State = Vec<Vec2>;
+
+    fn step(&mut self) {
+        self.pos.iter_mut().zip(&self.vels).for_each(|(a, b)| {
+            a.0 = (a.0 + b.0).rem_euclid(self.width); // wrap
+            a.1 = (a.1 + b.1).rem_euclid(self.height);
+        });
+    }
+
+    fn render(&self) -> Grid<char> {
+        let mut counts = Grid::new(self.height as usize, self.width as usize, 0);
+        self.pos.iter().for_each(|pos| counts[(pos.1, pos.0)] += 1);
+        counts.map(|count| match count {
+            0 => ' ',
+            1..=9 => char::from_digit(*count, 10).unwrap(),
+            _ => '*',
+        })
+    }
+
+    fn state(&self) -> Self::State {
+        self.pos.clone()
+    }
+}
+
 fn task1(input: String) -> String {
     let width = 101;
     let height = 103;
 
-    let seconds = 100;
-    let (mut pos, vels) = parse_input(input);
-    pos.iter_mut().zip(vels).for_each(|(a, b)| {
-        a.0 += b.0 * seconds;
-        a.1 += b.1 * seconds;
-        a.0 = a.0.rem_euclid(width); // wrap
-        a.1 = a.1.rem_euclid(height);
+    let (pos, vels) = parse_input(input);
+    let mut driver = Driver::new(Robots {
+        pos,
+        vels,
+        width,
+        height,
     });
+    driver.run(100);
+    let pos = &driver.simulation().pos;
 
     let x_middle = width / 2;
     let y_middle = height / 2;
@@ -77,57 +114,75 @@ fn task1(input: String) -> String {
     res.to_string()
 }
 
-fn draw(map: &[Vec<i32>]) {
-    print!("{}[2J", 27 as char);
-
-    map.iter().for_each(|line| {
-        line.iter().for_each(|num| {
-            if *num == 0 {
-                print!(" ");
-            } else {
-                print!("{}", num)
-            }
-        });
-        println!();
-    });
-}
 fn task2(input: String) -> String {
-    let width = 101;
-    let height = 103;
-
-    let (mut pos, vels) = parse_input(input);
-
-    for second in 0.. {
-        pos.iter_mut().zip(vels.iter()).for_each(|(pos, vel)| {
-            pos.0 += vel.0;
-            pos.1 += vel.1;
-            pos.0 = pos.0.rem_euclid(width);
-            pos.1 = pos.1.rem_euclid(height);
-        });
-
-        let mut map = vec![vec![0; width as usize]; height as usize];
-
-        for Vec2(x, y) in pos.iter() {
-            map[*y as usize][*x as usize] += 1;
-        }
+    tree_second(&robots(input)).to_string()
+}
 
-        // there seems to be some pattern with lot of bots in line 25
-        let should_draw = map[25].iter().sum::<i32>() > 27;
+/// `day14 show [FILE]`, drawing the robots at the second they form the tree
+fn show_tree(file: &str) {
+    let robots = robots(std::fs::read_to_string(file).unwrap());
+    let second = tree_second(&robots);
+    let mut driver = Driver::new(robots);
+    driver.fast_forward(second);
+    print!("{}[2J", 27 as char);
+    print!("{}", driver.simulation().render());
+    println!("second {}", second);
+}
 
-        //let should_draw = true;
-        if should_draw {
-            draw(&map);
-            println!("draw: on secs {}", second);
-            std::io::stdin().read_exact(&mut [0u8]).unwrap();
-        }
+fn robots(input: String) -> Robots {
+    let (pos, vels) = parse_input(input);
+    Robots {
+        pos,
+        vels,
+        width: 101,
+        height: 103,
     }
+}
 
-    "".to_string()
+/// First second the robots bunch up into the picture.
+fn tree_second(robots: &Robots) -> usize {
+    let Robots {
+        pos,
+        vels,
+        width,
+        height,
+    } = robots;
+
+    // x positions repeat every `width` seconds and y positions every `height`, so find the
+    // second each axis is most bunched up and combine the two
+    let bunched_at = |size: i32, axis: fn(&Vec2) -> i32| {
+        (0..size)
+            .min_by_key(|second| {
+                spread(
+                    pos.iter()
+                        .zip(vels)
+                        .map(|(pos, vel)| (axis(pos) + axis(vel) * second).rem_euclid(size)),
+                )
+            })
+            .unwrap()
+    };
+    let x_second = bunched_at(*width, |vec| vec.0);
+    let y_second = bunched_at(*height, |vec| vec.1);
+    let (second, _) = crt(&[
+        (x_second as i64, *width as i64),
+        (y_second as i64, *height as i64),
+    ])
+    .expect("width and height are coprime");
+    second as usize
+}
+
+/// Variance of the values, scaled by the squared count to stay in integers.
+fn spread(values: impl Iterator<Item = i32>) -> i64 {
+    let (count, sum, square_sum) = values.fold((0, 0, 0), |(count, sum, square_sum), value| {
+        let value = value as i64;
+        (count + 1, sum + value, square_sum + value * value)
+    });
+    count * square_sum - sum * sum
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{parse_input, task1};
+    use crate::{parse_input, task1, task2};
 
     const INPUT: &str = "p=0,4 v=3,-3
 p=6,3 v=-1,-3
@@ -147,6 +202,21 @@ p=9,5 v=-3,-3";
         parse_input(INPUT.to_string());
     }
 
+    #[test]
+    fn test_task2() {
+        // robots that all meet in a small square at second 4242, scattered otherwise
+        let input: Vec<String> = (0..200)
+            .map(|i: i32| {
+                let vel = ((i * 37) % 101 - 50, (i * 53) % 103 - 51);
+                let meet = (40 + i % 5, 50 + i / 5 % 5);
+                let x = (meet.0 - vel.0 * 4242).rem_euclid(101);
+                let y = (meet.1 - vel.1 * 4242).rem_euclid(103);
+                format!("p={},{} v={},{}", x, y, vel.0, vel.1)
+            })
+            .collect();
+        assert_eq!("4242", task2(input.join("\n")));
+    }
+
     #[test]
     fn test_task1() {
         let res = task1(INPUT.to_string());
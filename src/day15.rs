@@ -1,16 +1,11 @@
-use std::{
-    io::Read,
-    str::FromStr,
-    thread::{self, panicking},
-    time::{Duration, Instant},
-};
+use std::time::Instant;
 
-use advent_of_code_2024::task_argument_with_input;
-use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent},
-    terminal,
+use advent_of_code_2024::{
+    grid::Grid,
+    parse::sections,
+    simulation::{Driver, Simulation},
+    task_argument_with_input,
 };
-use rayon::iter;
 
 fn main() {
     let time = Instant::now();
@@ -39,20 +34,23 @@ struct Level {
 }
 
 impl Level {
-    fn draw(&self) {
-        print!("{}[2J", 27 as char);
-        //println!();
-        self.elements.iter().for_each(|line| {
-            line.iter().for_each(|elem| match elem {
-                Some(Element::Box) => print!("O"),
-                Some(Element::Wall) => print!("#"),
-                Some(Element::Player) => print!("@"),
-                Some(Element::WideboxA) => print!("["),
-                Some(Element::WideboxB) => print!("]"),
-                None => print!(" "),
-            });
-            println!();
-        });
+    fn draw(&self) -> Grid<char> {
+        let height = self.elements.len();
+        let width = self.elements.first().map_or(0, Vec::len);
+        let cells = self
+            .elements
+            .iter()
+            .flatten()
+            .map(|elem| match elem {
+                Some(Element::Box) => 'O',
+                Some(Element::Wall) => '#',
+                Some(Element::Player) => '@',
+                Some(Element::WideboxA) => '[',
+                Some(Element::WideboxB) => ']',
+                None => '.',
+            })
+            .collect();
+        Grid::from_vec(height, width, cells)
     }
 
     fn gps_sum(&self) -> usize {
@@ -116,7 +114,7 @@ impl Level {
     }
 
     fn move_chain(&mut self, pos: Pos, input: &Move, e: Element) {
-        let removed = std::mem::replace(&mut self.elements[pos.0][pos.1], Some(e));
+        let removed = self.elements[pos.0][pos.1].replace(e);
         if let Some(e) = removed {
             if *input == Move::Up || *input == Move::Down {
                 match e {
@@ -191,7 +189,7 @@ impl Level {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 enum Element {
     Wall,
     Box,
@@ -220,46 +218,70 @@ impl Move {
     }
 }
 
+/// The robot working through its list of moves, one per step.
+struct Warehouse {
+    level: Level,
+    moves: Vec<Move>,
+    next: usize,
+}
+
+impl Simulation for Warehouse {
+    type State = (usize, Vec<Vec<Option<Element>>>);
+
+    fn step(&mut self) {
+        self.level.player_move(&self.moves[self.next]);
+        self.next += 1;
+    }
+
+    fn is_done(&self) -> bool {
+        self.next == self.moves.len()
+    }
+
+    fn render(&self) -> Grid<char> {
+        self.level.draw()
+    }
+
+    fn state(&self) -> Self::State {
+        (self.next, self.level.elements.clone())
+    }
+}
+
+fn run_moves(level: Level, moves: Vec<Move>) -> Level {
+    let mut driver = Driver::new(Warehouse {
+        level,
+        moves,
+        next: 0,
+    });
+    driver.run_to_end();
+    driver.into_inner().level
+}
+
 fn parse_input(input: String) -> (Level, Vec<Move>) {
-    let moves: Vec<_> = input
-        .lines()
-        .skip_while(|line| line.starts_with("#"))
-        .skip(1)
-        .flat_map(|line| line.chars().map(Move::from_char))
-        .flatten()
+    let [level, moves] = sections(&input)[..] else {
+        panic!("Expected the map and moves separated by an empty line");
+    };
+
+    // anything else in the move list, such as line breaks, is skipped
+    let moves: Vec<_> = moves
+        .chars()
+        .filter_map(|char| Move::from_char(char).ok())
         .collect();
 
-    let level: Vec<&str> = input
-        .lines()
-        .take_while(|line| line.starts_with("#"))
-        .collect();
+    let level: Vec<&str> = level.lines().collect();
 
     let level = Level::from_vec_str(level).unwrap();
     (level, moves)
 }
 
 fn task1(input: String) -> String {
-    let (mut level, moves) = parse_input(input);
-
-    for m in moves {
-        level.player_move(&m);
-        //level.draw();
-        //thread::sleep(Duration::from_millis(1));
-    }
-    level.gps_sum().to_string()
+    let (level, moves) = parse_input(input);
+    run_moves(level, moves).gps_sum().to_string()
 }
 
 fn task2(input: String) -> String {
     let (mut level, moves) = parse_input(input);
     level.make_wide();
-
-    for m in moves {
-        level.player_move(&m);
-        //level.draw();
-        //thread::sleep(Duration::from_millis(1));
-    }
-
-    level.gps_sum().to_string()
+    run_moves(level, moves).gps_sum().to_string()
 }
 
 #[cfg(test)]
@@ -290,17 +312,22 @@ v^^>>><<^^<>>^v^<v^vv<>v^<<>^<^v^v><^<<<><<^<v><v<>vv>>v><v^<vv<>v^<<^";
 
     #[test]
     fn parse_test() {
-        parse_input(INPUT1.to_string());
+        let (_, moves) = parse_input(INPUT1.to_string());
+        assert_eq!(700, moves.len());
+
+        // stray characters between the moves are skipped
+        let input = INPUT1.replace('\n', "\r\n").replacen("<vv>", "< v\tv>", 1);
+        let (_, stray) = parse_input(input);
+        assert_eq!(moves, stray);
     }
 
     #[test]
     fn test_task1() {
-        task1(INPUT1.to_string());
+        assert_eq!("10092", task1(INPUT1.to_string()));
     }
 
     #[test]
     fn test_task2() {
-        let res = task2(INPUT1.to_string());
-        println!("res: {}", res)
+        assert_eq!("9021", task2(INPUT1.to_string()));
     }
 }
@@ -1,87 +1,328 @@
-use advent_of_code_2024::{get_input, task_argument};
+use std::{
+    env,
+    fmt::{self, Display},
+    ops::RangeInclusive,
+};
+
+use advent_of_code_2024::{get_input, parse::signed_integers, task_argument};
+use serde::Serialize;
 
 fn main() {
-    task_argument(task1, task2);
+    match env::args().nth(1).as_deref() {
+        Some("explain") => explain(env::args().skip(2).collect()),
+        _ => task_argument(task1, task2),
+    }
 }
 
 fn number_vecs(input_lines: Vec<String>) -> Vec<Vec<i32>> {
     input_lines
         .into_iter()
-        .map(|line| {
-            line.split_ascii_whitespace()
-                .map(|e| e.parse::<i32>().unwrap())
-                .collect::<Vec<_>>()
-        })
+        .map(|line| signed_integers(&line))
         .collect()
 }
 
+/// What makes a report safe: every step between neighbouring levels goes the
+/// same direction by an amount in `steps`.
+#[derive(Clone, Debug)]
+struct Rules {
+    steps: RangeInclusive<i32>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self { steps: 1..=3 }
+    }
+}
+
+impl Rules {
+    fn allows(&self, from: i32, to: i32, increasing: bool) -> bool {
+        let step = if increasing { to - from } else { from - to };
+        self.steps.contains(&step)
+    }
+}
+
 fn task1() {
     let input_lines = get_input();
     let number_vecs = number_vecs(input_lines);
-    let safe_count = number_vecs.iter().filter(|x| is_safe(x)).count();
+    let rules = Rules::default();
+    let safe_count = number_vecs.iter().filter(|x| is_safe(x, &rules)).count();
     println!("Safe count: {}", safe_count);
 }
 
-fn is_safe(numbers: &[i32]) -> bool {
-    let increasing = numbers[0] < numbers[1];
-    numbers
-        .windows(2)
-        .all(|elements| safe_window(elements, increasing))
+fn is_safe(numbers: &[i32], rules: &Rules) -> bool {
+    first_offence(numbers, rules).is_none()
 }
 
-fn safe_window(elements: &[i32], increasing: bool) -> bool {
-    let window_increasing = elements[0] < elements[1];
-    let diff = (elements[0] - elements[1]).abs();
-    let correct_diff = diff > 0 && diff < 4;
-    increasing == window_increasing && correct_diff
+/// Why a pair of neighbouring levels makes a report unsafe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Reason {
+    NoChange,
+    DirectionChange,
+    StepTooSmall,
+    StepTooBig,
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            Reason::NoChange => "no change",
+            Reason::DirectionChange => "direction change",
+            Reason::StepTooSmall => "step too small",
+            Reason::StepTooBig => "step too big",
+        };
+        f.write_str(reason)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+struct Offence {
+    indices: (usize, usize),
+    values: (i32, i32),
+    reason: Reason,
+}
+
+/// The first pair of neighbouring levels breaking the rules. The first pair
+/// decides whether the report should be increasing or decreasing.
+fn first_offence(numbers: &[i32], rules: &Rules) -> Option<Offence> {
+    let increasing = numbers.get(1).map(|second| numbers[0] < *second)?;
+    numbers
+        .windows(2)
+        .enumerate()
+        .find_map(|(index, elements)| {
+            let (from, to) = (elements[0], elements[1]);
+            let step = if increasing { to - from } else { from - to };
+            let reason = if rules.allows(from, to, increasing) {
+                return None;
+            } else if from == to {
+                Reason::NoChange
+            } else if step < 0 {
+                Reason::DirectionChange
+            } else if step < *rules.steps.start() {
+                Reason::StepTooSmall
+            } else {
+                Reason::StepTooBig
+            };
+            Some(Offence {
+                indices: (index, index + 1),
+                values: (from, to),
+                reason,
+            })
+        })
 }
 
 fn task2() {
     let input_lines = get_input();
     let number_vecs = number_vecs(input_lines);
-    let safe_count = number_vecs.iter().filter(|x| report_dampener(x)).count();
+    let rules = Rules::default();
+    let safe_count = number_vecs
+        .iter()
+        .filter(|x| report_dampener(x, &rules, 1))
+        .count();
     println!("Safe count: {}", safe_count);
 }
 
-fn report_dampener(numbers: &[i32]) -> bool {
-    let mut reverse = numbers.to_vec();
-    reverse.reverse();
+/// Whether removing at most `tolerance` levels makes the report safe.
+fn report_dampener(numbers: &[i32], rules: &Rules, tolerance: usize) -> bool {
+    min_removals_to_be_safe(numbers, rules).len() <= tolerance
+}
+
+/// Indices of the fewest levels to remove to make the report safe.
+///
+/// The levels that stay form the longest safe subsequence, found for each
+/// direction by extending the best subsequence ending at each earlier level.
+fn min_removals_to_be_safe(report: &[i32], rules: &Rules) -> Vec<usize> {
+    let mut best_kept: Vec<usize> = Vec::new();
+    for increasing in [true, false] {
+        // longest[i] is the length of the longest safe subsequence ending at i
+        let mut longest = vec![1; report.len()];
+        let mut previous: Vec<Option<usize>> = vec![None; report.len()];
+        for end in 0..report.len() {
+            for before in 0..end {
+                if longest[before] + 1 > longest[end]
+                    && rules.allows(report[before], report[end], increasing)
+                {
+                    longest[end] = longest[before] + 1;
+                    previous[end] = Some(before);
+                }
+            }
+        }
+
+        let mut current = (0..report.len()).rev().max_by_key(|index| longest[*index]);
+        if current.is_some_and(|last| longest[last] <= best_kept.len()) {
+            continue;
+        }
+        let mut kept = Vec::new();
+        while let Some(index) = current {
+            kept.push(index);
+            current = previous[index];
+        }
+        kept.reverse();
+        best_kept = kept;
+    }
+
+    (0..report.len())
+        .filter(|index| best_kept.binary_search(index).is_err())
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Verdict {
+    Safe,
+    /// Safe once the dampener removes some levels.
+    Dampened,
+    Unsafe,
+}
 
-    can_damp_problems(numbers) || can_damp_problems(&reverse)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+struct Explanation {
+    report: Vec<i32>,
+    verdict: Verdict,
+    offence: Option<Offence>,
+    /// Indices of the levels the dampener removes, empty unless dampened.
+    removed: Vec<usize>,
 }
 
-fn can_damp_problems(numbers: &[i32]) -> bool {
-    let Some((a, b)) = get_problem_indices(numbers) else {
-        return true; // no problems
+fn explain_report(report: &[i32], rules: &Rules, tolerance: usize) -> Explanation {
+    let offence = first_offence(report, rules);
+    let removals = match offence {
+        Some(_) => min_removals_to_be_safe(report, rules),
+        None => Vec::new(),
     };
-    let mut without_a = numbers.to_vec();
-    without_a.remove(a);
-    let mut without_b = numbers.to_vec();
-    without_b.remove(b);
-    get_problem_indices(&without_a).is_none() || get_problem_indices(&without_b).is_none()
-}
-
-fn get_problem_indices(numbers: &[i32]) -> Option<(usize, usize)> {
-    for (index, elem) in numbers.iter().enumerate() {
-        if let Some(next) = numbers.get(index + 1) {
-            let diff = (elem - next).abs();
-            if !(1..=3).contains(&diff) || elem > next {
-                return Some((index, index + 1));
+    let verdict = if offence.is_none() {
+        Verdict::Safe
+    } else if removals.len() <= tolerance {
+        Verdict::Dampened
+    } else {
+        Verdict::Unsafe
+    };
+    Explanation {
+        report: report.to_vec(),
+        verdict,
+        offence,
+        removed: if verdict == Verdict::Dampened {
+            removals
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+/// `day2 explain [--json] [--tolerance K] [--steps MIN-MAX]`, reading reports from stdin
+fn explain(args: Vec<String>) {
+    let mut json = false;
+    let mut tolerance = 1;
+    let mut rules = Rules::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--tolerance" => {
+                tolerance = args
+                    .next()
+                    .and_then(|k| k.parse().ok())
+                    .expect("--tolerance needs a number")
             }
+            "--steps" => {
+                let steps = args
+                    .next()
+                    .and_then(|steps| {
+                        let (min, max) = steps.split_once('-')?;
+                        Some(min.parse().ok()?..=max.parse().ok()?)
+                    })
+                    .expect("--steps needs a range like 1-3");
+                rules = Rules { steps };
+            }
+            _ => panic!("Unknown argument: {}", arg),
         }
     }
-    None
+
+    let explanations: Vec<_> = number_vecs(get_input())
+        .iter()
+        .map(|report| explain_report(report, &rules, tolerance))
+        .collect();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&explanations).unwrap());
+    } else {
+        print!("{}", explanation_table(&explanations));
+    }
+}
+
+fn explanation_table(explanations: &[Explanation]) -> String {
+    let rows: Vec<[String; 5]> = explanations
+        .iter()
+        .map(|explanation| {
+            let report: Vec<_> = explanation.report.iter().map(i32::to_string).collect();
+            let (window, reason) = match &explanation.offence {
+                Some(offence) => (
+                    format!(
+                        "{}-{} ({}, {})",
+                        offence.indices.0, offence.indices.1, offence.values.0, offence.values.1
+                    ),
+                    offence.reason.to_string(),
+                ),
+                None => (String::new(), String::new()),
+            };
+            let removed: Vec<_> = explanation
+                .removed
+                .iter()
+                .map(|index| format!("{} ({})", index, explanation.report[*index]))
+                .collect();
+            let verdict = match explanation.verdict {
+                Verdict::Safe => "safe",
+                Verdict::Dampened => "dampened",
+                Verdict::Unsafe => "unsafe",
+            };
+            [
+                report.join(" "),
+                verdict.to_string(),
+                window,
+                reason,
+                removed.join(", "),
+            ]
+        })
+        .collect();
+
+    let header = ["Report", "Verdict", "Window", "Reason", "Removed"].map(String::from);
+    let mut widths = header.clone().map(|title| title.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let mut table = String::new();
+    for row in [header].iter().chain(&rows) {
+        let cells: Vec<_> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        table.push_str(cells.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    fn is_safe(numbers: &[i32]) -> bool {
+        super::is_safe(numbers, &Rules::default())
+    }
+
+    fn report_dampener(numbers: &[i32]) -> bool {
+        super::report_dampener(numbers, &Rules::default(), 1)
+    }
+
     #[test]
     fn test_is_safe() {
         assert!(is_safe(&[1, 3, 4, 6, 9]));
         assert!(!is_safe(&[1, 3, 3, 6, 9]));
         assert!(!is_safe(&[9, 5, 4, 3, 2, 1]));
         assert!(is_safe(&[9, 6, 4, 3, 2, 1]));
+        assert!(is_safe(&[4]));
     }
 
     #[test]
@@ -95,5 +336,98 @@ mod tests {
 
         // should pass since we can just remove 15
         assert!(report_dampener(&[1, 3, 6, 9, 15]));
+        // the first level is the bad one
+        assert!(report_dampener(&[5, 1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn test_offences() {
+        let rules = Rules::default();
+        let reason = |numbers: &[i32]| first_offence(numbers, &rules).map(|o| o.reason);
+        assert_eq!(None, reason(&[7, 6, 4, 2, 1]));
+        assert_eq!(Some(Reason::StepTooBig), reason(&[1, 2, 7, 8, 9]));
+        assert_eq!(Some(Reason::DirectionChange), reason(&[1, 3, 2, 4, 5]));
+        assert_eq!(Some(Reason::NoChange), reason(&[8, 6, 4, 4, 1]));
+        assert_eq!(
+            Some(Reason::StepTooSmall),
+            first_offence(&[1, 3, 4], &Rules { steps: 2..=3 }).map(|o| o.reason)
+        );
+        assert_eq!(
+            Some(Offence {
+                indices: (1, 2),
+                values: (2, 7),
+                reason: Reason::StepTooBig
+            }),
+            first_offence(&[1, 2, 7, 8, 9], &rules)
+        );
+    }
+
+    #[test]
+    fn test_explanations() {
+        let rules = Rules::default();
+        let explanations: Vec<_> = [
+            vec![7, 6, 4, 2, 1],
+            vec![1, 2, 7, 8, 9],
+            vec![9, 7, 6, 2, 1],
+            vec![1, 3, 2, 4, 5],
+            vec![8, 6, 4, 4, 1],
+            vec![1, 3, 6, 7, 9],
+        ]
+        .iter()
+        .map(|report| explain_report(report, &rules, 1))
+        .collect();
+        let verdicts: Vec<_> = explanations.iter().map(|e| e.verdict).collect();
+        assert_eq!(
+            vec![
+                Verdict::Safe,
+                Verdict::Unsafe,
+                Verdict::Unsafe,
+                Verdict::Dampened,
+                Verdict::Dampened,
+                Verdict::Safe
+            ],
+            verdicts
+        );
+        assert_eq!(vec![3], explanations[4].removed);
+        assert!(explanations[1].removed.is_empty());
+
+        assert_eq!(
+            "Report     Verdict   Window      Reason            Removed
+7 6 4 2 1  safe
+1 2 7 8 9  unsafe    1-2 (2, 7)  step too big
+9 7 6 2 1  unsafe    2-3 (6, 2)  step too big
+1 3 2 4 5  dampened  1-2 (3, 2)  direction change  2 (2)
+8 6 4 4 1  dampened  2-3 (4, 4)  no change         3 (4)
+1 3 6 7 9  safe
+",
+            explanation_table(&explanations)
+        );
+    }
+
+    #[test]
+    fn test_min_removals() {
+        let rules = Rules::default();
+        assert_eq!(
+            Vec::<usize>::new(),
+            min_removals_to_be_safe(&[1, 2, 4], &rules)
+        );
+        assert_eq!(vec![3], min_removals_to_be_safe(&[8, 6, 4, 4, 1], &rules));
+        assert_eq!(
+            vec![1, 4],
+            min_removals_to_be_safe(&[1, 9, 2, 3, 3, 5], &rules)
+        );
+        assert_eq!(
+            vec![0, 1],
+            min_removals_to_be_safe(&[9, 8, 1, 2, 3], &rules)
+        );
+        assert!(!super::report_dampener(&[1, 9, 2, 3, 3, 5], &rules, 1));
+        assert!(super::report_dampener(&[1, 9, 2, 3, 3, 5], &rules, 2));
+
+        let wide = Rules { steps: 1..=7 };
+        assert_eq!(
+            Vec::<usize>::new(),
+            min_removals_to_be_safe(&[1, 8, 9], &wide)
+        );
+        assert_eq!(vec![0], min_removals_to_be_safe(&[1, 8, 9], &rules));
     }
 }
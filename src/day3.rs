@@ -1,105 +1,890 @@
-use std::{fs::File, io::Read};
+use std::{
+    env,
+    fmt::{self, Display},
+    fs::File,
+    io::{self, Read},
+    ops::{Range, RangeInclusive},
+    rc::Rc,
+};
 
-use advent_of_code_2024::{get_input, task_argument};
+use advent_of_code_2024::task_argument;
 use nom::{
-    branch::alt,
-    bytes::complete::{tag, take_until},
-    character::complete::{digit0, digit1},
-    combinator::{complete, map_res, not, peek},
-    multi::{many0, many1, many_till, separated_list1},
-    number,
-    sequence::{delimited, preceded, separated_pair, terminated},
-    IResult,
+    bytes::streaming::{tag, take_while_m_n},
+    character::{is_digit, streaming::char},
+    Err,
 };
 
 fn main() {
-    task_argument(task1, task2);
+    match env::args().nth(1).as_deref() {
+        Some("run") => run(env::args().skip(2).collect()),
+        Some("report") => report(env::args().skip(2).collect()),
+        _ => task_argument(task1, task2),
+    }
+}
+
+fn scan_file(file: &str, set: InstructionSet) -> impl Iterator<Item = Token> {
+    let file = File::open(file).unwrap();
+    Scanner::new(file, set).map(|token| token.expect("Failed to read input"))
 }
 
 fn task1() {
-    let mut f = File::open("inputs/input_day3_task1.txt").unwrap();
-    let mut string = String::new();
-    f.read_to_string(&mut string).unwrap();
-    let sum = parse_all(&string);
-    println!("Sum: {}", sum);
+    let tokens = scan_file("inputs/input_day3_task1.txt", InstructionSet::standard());
+    println!("Sum: {}", evaluate(tokens, Conditionals::Ignore));
 }
 
-// Parse a single number
-fn parse_a_number(input: &str) -> IResult<&str, i32> {
-    map_res(digit1, |s: &str| s.parse::<i32>())(input)
+fn task2() {
+    let tokens = scan_file("inputs/input_day3_task1.txt", InstructionSet::standard());
+    println!("Sum: {}", evaluate(tokens, Conditionals::Honour));
 }
 
-fn multiplication(input: &str) -> IResult<&str, i32> {
-    use nom::character::complete::char;
+/// `day3 run [--arithmetic] [--digits MIN-MAX] [--ignore | --nested] [FILE]`
+fn run(args: Vec<String>) {
+    let (file, set, conditionals) = options(args);
+    println!("Sum: {}", evaluate(scan_file(&file, set), conditionals));
+}
 
-    let (input, _) = tag("mul(")(input)?;
-    let (input, n1) = parse_a_number(input)?;
-    let (input, _) = char(',')(input)?;
-    let (input, n2) = parse_a_number(input)?;
-    let (input, _) = char(')')(input)?;
+/// `day3 report` with the same options as `run`
+fn report(args: Vec<String>) {
+    let (file, set, conditionals) = options(args);
+    let file = File::open(file).unwrap();
+    let report = Report::new(file, set, conditionals).expect("Failed to read input");
+    print!("{}", report);
+}
 
-    Ok((input, n1 * n2))
+fn options(args: Vec<String>) -> (String, InstructionSet, Conditionals) {
+    let mut file = "inputs/input_day3_task1.txt".to_string();
+    let mut set = InstructionSet::standard();
+    let mut conditionals = Conditionals::Honour;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--arithmetic" => set = set.with_arithmetic(),
+            "--digits" => {
+                let digits = args
+                    .next()
+                    .and_then(|digits| {
+                        let (min, max) = digits.split_once('-')?;
+                        Some(min.parse().ok()?..=max.parse().ok()?)
+                    })
+                    .expect("--digits needs a range like 1-3");
+                set = set.with_digits(digits);
+            }
+            "--ignore" => conditionals = Conditionals::Ignore,
+            "--nested" => conditionals = Conditionals::Nested,
+            _ => file = arg,
+        }
+    }
+    (file, set, conditionals)
 }
 
-//stop if mul( or don't()
-fn is_stop_sequence(input: &str) -> IResult<&str, ()> {
-    let _ = peek(alt((tag("mul("), tag("don't()"))))(input)?;
-    Ok((input, ()))
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Instruction {
+    Mul(u64, u64),
+    Do,
+    Dont,
+    /// Any other instruction with a value, already worked out from its arguments.
+    Call {
+        name: Rc<str>,
+        args: Vec<u64>,
+        value: i64,
+    },
 }
 
-fn skip_chars(input: &str) -> IResult<&str, ()> {
-    let (input, (_, _)) = many_till(nom::character::complete::anychar, is_stop_sequence)(input)?;
-    Ok((input, ()))
+/// An instruction and the bytes of the input it was read from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Token {
+    instruction: Instruction,
+    span: Range<usize>,
 }
 
-pub fn parse_all(input: &str) -> i32 {
-    let mut total = 0;
-    let mut remaining = input;
-    let mut enabled = true;
+/// Works out an instruction's value from its arguments.
+type ValueFn = Rc<dyn Fn(&[u64]) -> i64>;
+
+/// What an instruction does.
+#[derive(Clone)]
+enum Action {
+    Mul,
+    Enable,
+    Disable,
+    Value(ValueFn),
+}
+
+/// An instruction written `name(a,b,...)` with `arity` numbers.
+#[derive(Clone)]
+struct Definition {
+    name: Rc<str>,
+    arity: usize,
+    action: Action,
+}
+
+/// The instructions to look for, tried in the order they were added.
+#[derive(Clone)]
+struct InstructionSet {
+    definitions: Vec<Definition>,
+    /// How many digits a number may have.
+    digits: RangeInclusive<usize>,
+}
+
+impl InstructionSet {
+    fn new() -> Self {
+        Self {
+            definitions: Vec::new(),
+            digits: 1..=3,
+        }
+    }
+
+    /// `mul(a,b)`, `do()` and `don't()` with numbers of 1 to 3 digits.
+    fn standard() -> Self {
+        Self::new()
+            .define("mul", 2, Action::Mul)
+            .define("do", 0, Action::Enable)
+            .define("don't", 0, Action::Disable)
+    }
+
+    /// Adds `add(a,b)` and `sub(a,b)`.
+    fn with_arithmetic(self) -> Self {
+        self.register("add", 2, |args| args[0] as i64 + args[1] as i64)
+            .register("sub", 2, |args| args[0] as i64 - args[1] as i64)
+    }
+
+    /// At most 9 digits, so the product of two numbers fits an `i64`. Sums of
+    /// many of them don't, which is why [`evaluate`] adds up in `i128`.
+    fn with_digits(mut self, digits: RangeInclusive<usize>) -> Self {
+        assert!(
+            *digits.start() >= 1 && *digits.end() <= 9 && !digits.is_empty(),
+            "Numbers need between 1 and 9 digits"
+        );
+        self.digits = digits;
+        self
+    }
+
+    /// Adds an instruction whose value is `f` of its `arity` arguments.
+    fn register(self, name: &str, arity: usize, f: impl Fn(&[u64]) -> i64 + 'static) -> Self {
+        self.define(name, arity, Action::Value(Rc::new(f)))
+    }
+
+    fn define(mut self, name: &str, arity: usize, action: Action) -> Self {
+        assert!(!name.is_empty(), "Instructions need a name");
+        self.definitions.push(Definition {
+            name: name.into(),
+            arity,
+            action,
+        });
+        self
+    }
 
-    while !remaining.is_empty() {
-        if enabled {
-            // Stops if: mul & don't()
-            if let Ok((new_input, _)) = skip_chars(remaining) {
-                remaining = new_input;
+    /// Length of the longest instruction the set can read, name to `)`.
+    fn longest(&self) -> usize {
+        let max = *self.digits.end();
+        self.definitions
+            .iter()
+            .map(|definition| {
+                let arity = definition.arity;
+                definition.name.len() + 2 + arity * max + arity.saturating_sub(1)
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether an instruction can start with `byte`.
+    fn starts_with(&self, byte: u8) -> bool {
+        self.definitions
+            .iter()
+            .any(|definition| definition.name.as_bytes()[0] == byte)
+    }
+
+    /// Reads one instruction at the start of `input`. Like `alt`, an earlier
+    /// definition that runs out of input stops the later ones from being tried.
+    fn instruction<'a>(&self, input: &'a [u8]) -> Result<(&'a [u8], Instruction), CallError> {
+        let mut miss = None;
+        for definition in &self.definitions {
+            match self.call(definition, input) {
+                Err(CallError::NoMatch) => (),
+                // a later definition with the same name may still fit
+                Err(err @ CallError::Miss { .. }) => {
+                    miss.get_or_insert(err);
+                }
+                result => return result,
             }
+        }
+        Err(miss.unwrap_or(CallError::NoMatch))
+    }
 
-            if let Ok((_, _)) = peek(tag::<_, _, ()>("don't"))(remaining) {
-                enabled = false;
-                continue;
+    // The parsers are streaming: running out of input is `Incomplete`, not an
+    // error, so the scanner knows to read more before giving up on a candidate.
+    // Bounding the digits bounds how much input a candidate can hold back.
+
+    fn number<'a>(&self, input: &'a [u8]) -> Result<(&'a [u8], u64), Failure> {
+        let (min, max) = (*self.digits.start(), *self.digits.end());
+        match take_while_m_n::<_, _, nom::error::Error<_>>(min, max, is_digit)(input) {
+            Ok((rest, _)) if rest.first().is_some_and(u8::is_ascii_digit) => Err(Failure::Miss {
+                reason: Reason::TooManyDigits { max },
+                consumed: max,
+            }),
+            Ok((rest, digits)) => {
+                let number = std::str::from_utf8(digits).unwrap().parse().unwrap();
+                Ok((rest, number))
+            }
+            Err(Err::Incomplete(_)) => Err(Failure::Incomplete),
+            Err(_) => {
+                let digits = input
+                    .iter()
+                    .take_while(|byte| byte.is_ascii_digit())
+                    .count();
+                let reason = match digits {
+                    0 => Reason::ExpectedNumber { found: input[0] },
+                    _ => Reason::TooFewDigits { min },
+                };
+                Err(Failure::Miss {
+                    reason,
+                    consumed: digits,
+                })
             }
+        }
+    }
+
+    fn call<'a>(
+        &self,
+        definition: &Definition,
+        input: &'a [u8],
+    ) -> Result<(&'a [u8], Instruction), CallError> {
+        let candidate = input;
+        let mut input = match tag::<_, _, nom::error::Error<_>>(definition.name.as_bytes())(input)
+            .and_then(|(rest, _)| char('(')(rest))
+        {
+            Ok((rest, _)) => rest,
+            Err(Err::Incomplete(_)) => return Err(CallError::Incomplete { started: false }),
+            Err(_) => return Err(CallError::NoMatch),
+        };
 
-            if let Ok((new_input, result)) = multiplication(remaining) {
-                total += result;
-                remaining = new_input;
-            } else if !remaining.is_empty() {
-                remaining = &remaining[1..];
+        // from here on a failure is a near miss
+        let miss = |rest: &[u8], failure| match failure {
+            Failure::Incomplete => CallError::Incomplete { started: true },
+            Failure::Miss { reason, consumed } => CallError::Miss {
+                name: definition.name.clone(),
+                length: candidate.len() - rest.len() + consumed,
+                reason,
+            },
+        };
+        let mut args = Vec::with_capacity(definition.arity);
+        for index in 0..definition.arity {
+            if index > 0 {
+                input = expect(input, ',').map_err(|failure| miss(input, failure))?;
             }
-        } else if let Ok((new_input, _)) = skip_until_do(remaining) {
-            remaining = new_input;
-            enabled = true;
+            let (rest, arg) = self.number(input).map_err(|failure| miss(input, failure))?;
+            args.push(arg);
+            input = rest;
         }
+        input = expect(input, ')').map_err(|failure| miss(input, failure))?;
+
+        let instruction = match &definition.action {
+            Action::Mul => Instruction::Mul(args[0], args[1]),
+            Action::Enable => Instruction::Do,
+            Action::Disable => Instruction::Dont,
+            Action::Value(f) => Instruction::Call {
+                name: definition.name.clone(),
+                value: f(&args),
+                args,
+            },
+        };
+        Ok((input, instruction))
+    }
+}
+
+fn expect(input: &[u8], expected: char) -> Result<&[u8], Failure> {
+    match char::<_, nom::error::Error<_>>(expected)(input) {
+        Ok((rest, _)) => Ok(rest),
+        Err(Err::Incomplete(_)) => Err(Failure::Incomplete),
+        Err(_) => Err(Failure::Miss {
+            reason: Reason::Expected {
+                expected,
+                found: input[0],
+            },
+            consumed: 0,
+        }),
     }
-    total
 }
 
-fn skip_until_do(input: &str) -> IResult<&str, ()> {
-    let (input, (_, _)) = many_till(nom::character::complete::anychar, peek(tag("do()")))(input)?;
-    Ok((input, ()))
+/// Why a candidate instruction, `name(` and onwards, didn't parse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Reason {
+    Expected { expected: char, found: u8 },
+    ExpectedNumber { found: u8 },
+    TooFewDigits { min: usize },
+    TooManyDigits { max: usize },
+    InputEnded,
 }
 
-fn task2() {
-    //Accidentally edited task1 code to solve task2
+impl Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reason::Expected { expected, found } => write!(
+                f,
+                "expected '{}' but found '{}'",
+                expected,
+                found.escape_ascii()
+            ),
+            Reason::ExpectedNumber { found } => {
+                write!(f, "expected a number but found '{}'", found.escape_ascii())
+            }
+            Reason::TooFewDigits { min } => write!(f, "number has fewer than {} digits", min),
+            Reason::TooManyDigits { max } => write!(f, "number has more than {} digits", max),
+            Reason::InputEnded => write!(f, "input ended"),
+        }
+    }
+}
+
+/// How a part of an instruction failed, `consumed` bytes into that part.
+enum Failure {
+    Incomplete,
+    Miss { reason: Reason, consumed: usize },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum CallError {
+    /// Not this instruction.
+    NoMatch,
+    /// Ran out of input, `started` once the name and `(` matched.
+    Incomplete { started: bool },
+    /// The name and `(` matched, but the byte `length` bytes in didn't fit.
+    Miss {
+        name: Rc<str>,
+        length: usize,
+        reason: Reason,
+    },
+}
+
+/// A candidate instruction that failed to parse.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct NearMiss {
+    name: Rc<str>,
+    /// From the start of the name up to and including the byte that failed.
+    span: Range<usize>,
+    reason: Reason,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Scanned {
+    Token(Token),
+    NearMiss(NearMiss),
+}
+
+/// Reads instructions from `reader` a chunk at a time. Only the current chunk,
+/// an instruction cut off at its end and any context asked for are kept in
+/// memory.
+struct Scanner<R> {
+    reader: R,
+    set: InstructionSet,
+    chunk_size: usize,
+    buffer: Vec<u8>,
+    /// Offset in the whole input of `buffer[0]`.
+    offset: usize,
+    /// Start of the part of `buffer` not scanned yet.
+    position: usize,
+    /// Bytes kept in `buffer` before `position`.
+    behind: usize,
+    /// Bytes read past `position` before scanning on.
+    ahead: usize,
+    ended: bool,
+}
+
+impl<R: Read> Scanner<R> {
+    fn new(reader: R, set: InstructionSet) -> Self {
+        Self::with_chunk_size(reader, set, 64 * 1024)
+    }
+
+    fn with_chunk_size(reader: R, set: InstructionSet, chunk_size: usize) -> Self {
+        Self {
+            reader,
+            set,
+            chunk_size,
+            buffer: Vec::new(),
+            offset: 0,
+            position: 0,
+            behind: 0,
+            ahead: 0,
+            ended: false,
+        }
+    }
+
+    /// Keeps `context` bytes around every instruction and near miss in the
+    /// buffer when it is returned, for [`Scanner::buffered`].
+    fn with_context(mut self, context: usize) -> Self {
+        self.behind = context;
+        self.ahead = context + self.set.longest();
+        self
+    }
+
+    /// The part of the input in `range` that is still in the buffer.
+    fn buffered(&self, range: Range<usize>) -> &[u8] {
+        let end = self.offset + self.buffer.len();
+        let start = range.start.clamp(self.offset, end);
+        let end = range.end.clamp(start, end);
+        &self.buffer[start - self.offset..end - self.offset]
+    }
+
+    /// Drops the scanned part of the buffer, but for the last `behind` bytes,
+    /// and reads the next chunk after the rest.
+    fn refill(&mut self) -> io::Result<()> {
+        let dropped = self.position.saturating_sub(self.behind);
+        self.buffer.drain(..dropped);
+        self.offset += dropped;
+        self.position -= dropped;
+
+        let filled = self.buffer.len();
+        self.buffer.resize(filled + self.chunk_size, 0);
+        let result = loop {
+            match self.reader.read(&mut self.buffer[filled..]) {
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result,
+            }
+        };
+        self.buffer
+            .truncate(filled + result.as_ref().map_or(0, |read| *read));
+        self.ended = result? == 0;
+        Ok(())
+    }
+}
+
+impl<R: Read> Scanner<R> {
+    /// The next instruction or near miss.
+    fn next_scanned(&mut self) -> Option<io::Result<Scanned>> {
+        loop {
+            while self.position < self.buffer.len()
+                && (self.ended || self.buffer.len() - self.position > self.ahead)
+            {
+                let rest = &self.buffer[self.position..];
+                let start = self.offset + self.position;
+                let near_miss = match self.set.instruction(rest) {
+                    Ok((after, instruction)) => {
+                        self.position += rest.len() - after.len();
+                        return Some(Ok(Scanned::Token(Token {
+                            instruction,
+                            span: start..self.offset + self.position,
+                        })));
+                    }
+                    // might still become an instruction with the next chunk
+                    Err(CallError::Incomplete { .. }) if !self.ended => break,
+                    Err(CallError::Incomplete { started: true }) => Some(NearMiss {
+                        name: self.candidate_name(rest),
+                        span: start..start + rest.len(),
+                        reason: Reason::InputEnded,
+                    }),
+                    Err(CallError::Miss {
+                        name,
+                        length,
+                        reason,
+                    }) => Some(NearMiss {
+                        name,
+                        span: start..start + length + 1,
+                        reason,
+                    }),
+                    Err(_) => None,
+                };
+
+                let skip = rest[1..]
+                    .iter()
+                    .position(|byte| self.set.starts_with(*byte))
+                    .map_or(rest.len(), |index| index + 1);
+                self.position += skip;
+                if let Some(near_miss) = near_miss {
+                    return Some(Ok(Scanned::NearMiss(near_miss)));
+                }
+            }
+            if self.ended {
+                return None;
+            }
+            if let Err(err) = self.refill() {
+                return Some(Err(err));
+            }
+        }
+    }
+
+    /// Name of the first instruction whose name and `(` start `candidate`.
+    fn candidate_name(&self, candidate: &[u8]) -> Rc<str> {
+        self.set
+            .definitions
+            .iter()
+            .find(|definition| {
+                candidate.starts_with(definition.name.as_bytes())
+                    && candidate.get(definition.name.len()) == Some(&b'(')
+            })
+            .map(|definition| definition.name.clone())
+            .unwrap()
+    }
+}
+
+impl<R: Read> Iterator for Scanner<R> {
+    type Item = io::Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_scanned()? {
+                Ok(Scanned::Token(token)) => return Some(Ok(token)),
+                Ok(Scanned::NearMiss(_)) => (),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Conditionals {
+    /// Every instruction counts.
+    Ignore,
+    /// Instructions after a `don't()` don't count until the next `do()`.
+    Honour,
+    /// Every `don't()` needs its own `do()` before instructions count again.
+    Nested,
+}
+
+/// Sum of the instructions that count. Each value fits an `i64`, the sum
+/// is kept in an `i128` so any realistic number of them can't overflow.
+fn evaluate(tokens: impl IntoIterator<Item = Token>, conditionals: Conditionals) -> i128 {
+    let mut disabled: usize = 0;
+    let mut sum = 0;
+    for token in tokens {
+        let value = match token.instruction {
+            Instruction::Mul(a, b) => a as i128 * b as i128,
+            Instruction::Call { value, .. } => value as i128,
+            Instruction::Do => {
+                disabled = match conditionals {
+                    Conditionals::Nested => disabled.saturating_sub(1),
+                    _ => 0,
+                };
+                continue;
+            }
+            Instruction::Dont => {
+                disabled += 1;
+                continue;
+            }
+        };
+        if disabled == 0 || conditionals == Conditionals::Ignore {
+            sum += value;
+        }
+    }
+    sum
+}
+
+/// A valued instruction that didn't count because of a `don't()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Disabled {
+    token: Token,
+    /// Span of the `don't()` that disabled it.
+    by: Range<usize>,
+    /// The instruction as written.
+    text: String,
+}
+
+/// Everything in the input that looks like an instruction but doesn't count.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Report {
+    /// Each near miss with some input around it, escaped to one line.
+    near_misses: Vec<(NearMiss, String)>,
+    disabled: Vec<Disabled>,
+}
+
+impl Report {
+    const CONTEXT: usize = 10;
+
+    fn new(reader: impl Read, set: InstructionSet, conditionals: Conditionals) -> io::Result<Self> {
+        Self::scan(
+            Scanner::new(reader, set).with_context(Self::CONTEXT),
+            conditionals,
+        )
+    }
+
+    /// Reads the report off `scanner`, taking the text of each entry from its
+    /// buffer as the entry comes up.
+    fn scan<R: Read>(mut scanner: Scanner<R>, conditionals: Conditionals) -> io::Result<Self> {
+        let mut near_misses = Vec::new();
+        let mut disabled = Vec::new();
+        // the don't()s in effect, innermost last
+        let mut disabled_by: Vec<Range<usize>> = Vec::new();
+        while let Some(scanned) = scanner.next_scanned() {
+            let text = |span: Range<usize>| scanner.buffered(span).escape_ascii().to_string();
+            let token = match scanned? {
+                Scanned::NearMiss(near_miss) => {
+                    let start = near_miss.span.start.saturating_sub(Self::CONTEXT);
+                    let context = text(start..near_miss.span.end + Self::CONTEXT);
+                    near_misses.push((near_miss, context));
+                    continue;
+                }
+                Scanned::Token(token) => token,
+            };
+            match token.instruction {
+                Instruction::Do if conditionals == Conditionals::Nested => {
+                    disabled_by.pop();
+                }
+                Instruction::Do => disabled_by.clear(),
+                Instruction::Dont => disabled_by.push(token.span.clone()),
+                _ if conditionals == Conditionals::Ignore => (),
+                _ => {
+                    if let Some(by) = disabled_by.last() {
+                        disabled.push(Disabled {
+                            by: by.clone(),
+                            text: text(token.span.clone()),
+                            token,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(Self {
+            near_misses,
+            disabled,
+        })
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} near misses", self.near_misses.len())?;
+        for (near_miss, context) in &self.near_misses {
+            writeln!(
+                f,
+                "  at {}: {}( {}, in \"{}\"",
+                near_miss.span.start, near_miss.name, near_miss.reason, context
+            )?;
+        }
+        writeln!(f, "{} instructions disabled", self.disabled.len())?;
+        for disabled in &self.disabled {
+            writeln!(
+                f,
+                "  at {}: {} disabled by the don't() at {}",
+                disabled.token.span.start, disabled.text, disabled.by.start
+            )?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io;
+
+    use crate::{
+        evaluate, CallError, Conditionals, Instruction, InstructionSet, NearMiss, Reason, Report,
+        Scanner, Token,
+    };
+
+    const INPUT1: &str = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+    const INPUT2: &str =
+        "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
 
-    use crate::parse_all;
+    /// Every instruction in the corrupted memory, skipping everything else.
+    fn tokenize(input: &str) -> Vec<Token> {
+        scan(input, InstructionSet::standard())
+    }
+
+    fn scan(input: &str, set: InstructionSet) -> Vec<Token> {
+        Scanner::new(input.as_bytes(), set)
+            .collect::<io::Result<_>>()
+            .expect("Reading from memory cannot fail")
+    }
 
     #[test]
     fn simple() {
-        let res = parse_all("asd mul(3,3), mul(5,5x jeps mul(2,2)");
-        println!("res: {:?}", res);
+        let tokens = tokenize("asd mul(3,3), mul(5,5x jeps mul(2,2)");
+        assert_eq!(
+            vec![
+                Token {
+                    instruction: Instruction::Mul(3, 3),
+                    span: 4..12
+                },
+                Token {
+                    instruction: Instruction::Mul(2, 2),
+                    span: 28..36
+                }
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_task1() {
+        assert_eq!(161, evaluate(tokenize(INPUT1), Conditionals::Ignore));
+    }
+
+    #[test]
+    fn test_task2() {
+        let tokens = tokenize(INPUT2);
+        assert_eq!(161, evaluate(tokens.clone(), Conditionals::Ignore));
+        assert_eq!(48, evaluate(tokens.clone(), Conditionals::Honour));
+        assert_eq!(
+            Some(&Token {
+                instruction: Instruction::Dont,
+                span: 20..27
+            }),
+            tokens.get(1)
+        );
+    }
+
+    #[test]
+    fn chunk_boundaries() {
+        let input = format!("{}do(don't()mul(999,2)mul(1000,2)mul(7,7", INPUT2);
+        let expected = tokenize(&input);
+        assert_eq!(8, expected.len());
+        for chunk_size in 1..=input.len() {
+            let set = InstructionSet::standard().with_arithmetic();
+            let tokens: Vec<_> = Scanner::with_chunk_size(input.as_bytes(), set, chunk_size)
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(expected, tokens, "chunk size {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn digit_bounds() {
+        let input = "mul(1,22)mul(333,4444)mul(55555,6)";
+        assert_eq!(22, evaluate(tokenize(input), Conditionals::Ignore));
+        let wide = InstructionSet::standard().with_digits(1..=5);
+        assert_eq!(
+            22 + 333 * 4444 + 55555 * 6,
+            evaluate(scan(input, wide), Conditionals::Ignore)
+        );
+        let strict = InstructionSet::standard().with_digits(2..=3);
+        assert_eq!(0, evaluate(scan(input, strict), Conditionals::Ignore));
+
+        // every product fits an i64, ten of them added up don't
+        let input = "mul(999999999,999999999)".repeat(10);
+        let widest = InstructionSet::standard().with_digits(1..=9);
+        assert_eq!(
+            999999999_i128 * 999999999 * 10,
+            evaluate(scan(&input, widest), Conditionals::Ignore)
+        );
+        let input = "max()".repeat(3);
+        let large = InstructionSet::new().register("max", 0, |_| i64::MAX);
+        assert_eq!(
+            i64::MAX as i128 * 3,
+            evaluate(scan(&input, large), Conditionals::Ignore)
+        );
+    }
+
+    #[test]
+    fn instruction_sets() {
+        let input = "add(2,3)sub(1,10)don't()mul(2,2)don't()add(1,1)do()mul(3,3)do()sub(4,1)";
+        // add and sub aren't in the standard set
+        assert_eq!(13, evaluate(tokenize(input), Conditionals::Ignore));
+
+        let arithmetic = InstructionSet::standard().with_arithmetic();
+        let tokens = scan(input, arithmetic.clone());
+        assert_eq!(14, evaluate(tokens.clone(), Conditionals::Ignore));
+        assert_eq!(8, evaluate(tokens.clone(), Conditionals::Honour));
+        assert_eq!(-1, evaluate(tokens, Conditionals::Nested));
+
+        let custom = arithmetic
+            .register("neg", 1, |args| -(args[0] as i64))
+            .register("max", 3, |args| *args.iter().max().unwrap() as i64);
+        let tokens = scan("neg(5)max(1,9,4)max(1,2)", custom);
+        assert_eq!(4, evaluate(tokens.clone(), Conditionals::Honour));
+        assert_eq!(
+            Instruction::Call {
+                name: "max".into(),
+                args: vec![1, 9, 4],
+                value: 9
+            },
+            tokens[1].instruction
+        );
+    }
+
+    #[test]
+    fn near_misses() {
+        let input = "asd mul(3,3), mul(5,5x jeps mul(1234,2)do(!mul(1,";
+        let report = Report::new(
+            input.as_bytes(),
+            InstructionSet::standard(),
+            Conditionals::Honour,
+        )
+        .unwrap();
+        let near_miss = |span, reason| NearMiss {
+            name: if span == (39..43) { "do" } else { "mul" }.into(),
+            span,
+            reason,
+        };
+        assert_eq!(
+            vec![
+                near_miss(
+                    14..22,
+                    Reason::Expected {
+                        expected: ')',
+                        found: b'x'
+                    }
+                ),
+                near_miss(28..36, Reason::TooManyDigits { max: 3 }),
+                near_miss(
+                    39..43,
+                    Reason::Expected {
+                        expected: ')',
+                        found: b'!'
+                    }
+                ),
+                near_miss(43..49, Reason::InputEnded),
+            ],
+            report
+                .near_misses
+                .iter()
+                .map(|(near_miss, _)| near_miss.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "4 near misses
+  at 14: mul( expected ')' but found 'x', in \"mul(3,3), mul(5,5x jeps mul(\"
+  at 28: mul( number has more than 3 digits, in \"5,5x jeps mul(1234,2)do(!mul\"
+  at 39: do( expected ')' but found '!', in \"ul(1234,2)do(!mul(1,\"
+  at 43: mul( input ended, in \"234,2)do(!mul(1,\"
+0 instructions disabled
+",
+            report.to_string()
+        );
+
+        // the contexts don't depend on where the chunks end
+        for chunk_size in 1..=input.len() {
+            let set = InstructionSet::standard();
+            let scanner = Scanner::with_chunk_size(input.as_bytes(), set, chunk_size)
+                .with_context(Report::CONTEXT);
+            let chunked = Report::scan(scanner, Conditionals::Honour).unwrap();
+            assert_eq!(report, chunked, "chunk size {}", chunk_size);
+        }
+
+        let reason = |input: &str| match InstructionSet::standard().instruction(input.as_bytes()) {
+            Err(CallError::Miss { reason, .. }) => reason.to_string(),
+            _ => panic!("{} is not a near miss", input),
+        };
+        assert_eq!("expected ',' but found ';'", reason("mul(1;2)"));
+        assert_eq!("expected a number but found 'x'", reason("mul(x,2)"));
+    }
+
+    #[test]
+    fn disabled_report() {
+        let report = Report::new(
+            INPUT2.as_bytes(),
+            InstructionSet::standard(),
+            Conditionals::Honour,
+        )
+        .unwrap();
+        let disabled: Vec<_> = report
+            .disabled
+            .iter()
+            .map(|disabled| (disabled.token.instruction.clone(), disabled.by.clone()))
+            .collect();
+        assert_eq!(
+            vec![
+                (Instruction::Mul(5, 5), 20..27),
+                (Instruction::Mul(11, 8), 20..27)
+            ],
+            disabled
+        );
+        assert!(report
+            .to_string()
+            .ends_with("  at 48: mul(11,8) disabled by the don't() at 20\n"));
+
+        let report = Report::new(
+            INPUT2.as_bytes(),
+            InstructionSet::standard(),
+            Conditionals::Ignore,
+        )
+        .unwrap();
+        assert!(report.disabled.is_empty());
     }
 }
@@ -1,10 +1,20 @@
-use std::time::{self, Instant};
+use std::{env, time::Instant};
 
-use advent_of_code_2024::{get_input, task_argument};
-use rayon::{iter::ParallelIterator, slice::ParallelSlice};
+use advent_of_code_2024::{
+    grid::Grid,
+    pattern::{Pattern, Placement},
+    task_argument,
+    transform::Transform,
+    word_search::{ascii_grid, find_words, Letter, WordMatch, ROWS_PER_BAND},
+};
+use crossterm::style::{Color, Stylize};
 
 fn main() {
-    task_argument(task1, task2);
+    match env::args().nth(1).as_deref() {
+        Some("words") => word_search(env::args().skip(2).collect()),
+        Some("matches") => match_listing(env::args().skip(2).collect()),
+        _ => task_argument(task1, task2),
+    }
 }
 
 fn task1() {
@@ -18,83 +28,233 @@ fn task1() {
     println!("res: {}", res);
 }
 
-fn solve1(input_lines: Vec<String>) -> i32 {
-    let input_lines: Vec<_> = input_lines
-        .into_iter()
-        .map(|string| string.chars().collect::<Vec<char>>())
-        .collect();
+fn solve1(input_lines: Vec<String>) -> usize {
+    let input = input_lines.join("\n");
+    match ascii_grid(&input) {
+        Some(grid) => find_xmas(&grid).len(),
+        None => find_xmas(&Grid::parse(&input, |char| char)).len(),
+    }
+}
 
-    let mut total_hits = 0;
-    for (y, line) in input_lines.iter().enumerate() {
-        for (x, char) in line.iter().enumerate() {
-            if *char == 'X' {
-                total_hits += check_xmas(y, x, &input_lines);
+fn find_xmas<T: Letter>(grid: &Grid<T>) -> Vec<WordMatch> {
+    find_words(grid, &[b"XMAS"], ROWS_PER_BAND)
+}
+
+/// `day4 words [--grid FILE] [--file WORDS] [--highlight] [WORD...]`
+fn word_search(args: Vec<String>) {
+    let mut grid_file = "inputs/input_day4.txt".to_string();
+    let mut words = Vec::new();
+    let mut highlight = false;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--grid" => grid_file = args.next().expect("--grid needs a file"),
+            "--file" => {
+                let file = args.next().expect("--file needs a file");
+                let content = std::fs::read_to_string(file).unwrap();
+                words.extend(content.split_whitespace().map(|word| word.to_string()));
             }
+            "--highlight" => highlight = true,
+            _ => words.push(arg),
+        }
+    }
+    if words.is_empty() {
+        eprintln!("Provide words to search for, or --file with one word per line");
+        return;
+    }
+
+    let input = std::fs::read_to_string(grid_file).unwrap();
+    match ascii_grid(&input) {
+        Some(grid) if words.iter().all(|word| word.is_ascii()) => {
+            print_words(&grid, &words, highlight)
         }
+        _ => print_words(&Grid::parse(&input, |char| char), &words, highlight),
     }
-    total_hits
 }
 
-fn check_xmas(y: usize, x: usize, input_lines: &Vec<Vec<char>>) -> i32 {
-    //count hits
+fn print_words<T: Letter + Into<char>>(grid: &Grid<T>, words: &[String], highlight: bool) {
+    let matches = find_words(grid, words, ROWS_PER_BAND);
+    for hit in &matches {
+        println!(
+            "{} at {:?} going {}",
+            words[hit.word],
+            hit.start,
+            direction_name(hit.direction)
+        );
+    }
+    println!("{} matches", matches.len());
+
+    if highlight {
+        print_highlighted(grid, words, &matches);
+    }
+}
 
-    let directions: Vec<(i32, i32)> = vec![
-        (1, 0),   //N
-        (1, 1),   //NE
-        (0, 1),   //E
-        (-1, 1),  //SE
-        (-1, 0),  //S
-        (-1, -1), //SW
-        (0, -1),  //W
-        (1, -1),  //NW
-    ];
+fn direction_name(direction: (i32, i32)) -> &'static str {
+    match direction {
+        (-1, 0) => "N",
+        (-1, 1) => "NE",
+        (0, 1) => "E",
+        (1, 1) => "SE",
+        (1, 0) => "S",
+        (1, -1) => "SW",
+        (0, -1) => "W",
+        (-1, -1) => "NW",
+        // single letters
+        (0, 0) => "-",
+        _ => "?",
+    }
+}
 
-    let mut hits = 0;
-    for dir in directions {
-        if let Ok(()) = check_direction(y as i32, x as i32, dir, input_lines) {
-            hits += 1;
+fn print_highlighted<T: Letter + Into<char>>(
+    grid: &Grid<T>,
+    words: &[String],
+    matches: &[WordMatch],
+) {
+    let mut covered = grid.map(|_| false);
+    for hit in matches {
+        for index in 0..words[hit.word].chars().count() as i32 {
+            covered[(
+                hit.start.0 + hit.direction.0 * index,
+                hit.start.1 + hit.direction.1 * index,
+            )] = true;
         }
     }
-    hits
-}
-
-fn check_direction(
-    y: i32,
-    x: i32,
-    direction: (i32, i32),
-    input_lines: &Vec<Vec<char>>,
-) -> Result<(), ()> {
-    let mut index = 0;
-    while let Ok(char) = get_char_in_coord(
-        (y + direction.0 * index, x + direction.1 * index),
-        input_lines,
-    ) {
-        let hit = match index {
-            0 => char == 'X',
-            1 => char == 'M',
-            2 => char == 'A',
-            3 => char == 'S',
-            _ => true,
-        };
-        if !hit {
-            return Err(());
+
+    for (y, row) in grid.rows().enumerate() {
+        for (x, letter) in row.iter().enumerate() {
+            let char: char = (*letter).into();
+            if covered[(y as i32, x as i32)] {
+                print!("{}", char.red().bold());
+            } else {
+                print!("{}", char.dark_grey());
+            }
         }
-        if index == 3 {
-            return Ok(());
+        println!();
+    }
+}
+
+/// `day4 matches [--grid FILE] [--part 1|2] [--no-list]`, listing the XMAS and
+/// X-MAS matches and showing how many of them cover each letter
+fn match_listing(args: Vec<String>) {
+    let mut grid_file = "inputs/input_day4.txt".to_string();
+    let mut parts = vec![1, 2];
+    let mut list = true;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--grid" => grid_file = args.next().expect("--grid needs a file"),
+            "--part" => {
+                let part = args
+                    .next()
+                    .and_then(|part| part.parse().ok())
+                    .filter(|part| [1, 2].contains(part))
+                    .expect("--part needs 1 or 2");
+                parts = vec![part];
+            }
+            "--no-list" => list = false,
+            _ => panic!("Unknown argument: {}", arg),
         }
-        index += 1;
     }
-    Err(())
+
+    let input = std::fs::read_to_string(grid_file).unwrap();
+    match ascii_grid(&input) {
+        Some(grid) => show_matches(&grid, &parts, list),
+        None => show_matches(&Grid::parse(&input, |char| char), &parts, list),
+    }
 }
 
-fn get_char_in_coord(coord: (i32, i32), input_lines: &Vec<Vec<char>>) -> Result<char, ()> {
-    if coord.0 < 0 && coord.1 < 0 {
-        return Err(());
+fn show_matches<T: Letter + Into<char> + From<u8>>(grid: &Grid<T>, parts: &[u32], list: bool) {
+    for &part in parts {
+        let heat = if part == 1 {
+            let matches = find_xmas(grid);
+            if list {
+                for hit in &matches {
+                    println!(
+                        "XMAS at {:?} going {}",
+                        hit.start,
+                        direction_name(hit.direction)
+                    );
+                }
+            }
+            println!("part 1: {} matches", matches.len());
+            word_heatmap(grid, &matches, 4)
+        } else {
+            let placements = find_x_mas(grid);
+            if list {
+                for placement in &placements {
+                    println!(
+                        "X-MAS at {:?} turned {:?}",
+                        placement.pos, placement.transform
+                    );
+                }
+            }
+            println!("part 2: {} matches", placements.len());
+            x_mas_heatmap(grid, &placements)
+        };
+        print_heatmap(grid, &heat);
     }
+}
 
-    let coord = (coord.0 as usize, coord.1 as usize);
-    let char = input_lines.get(coord.0).ok_or(())?.get(coord.1).ok_or(())?;
-    Ok(*char)
+/// How many matches of a `length` letter word cover each cell.
+fn word_heatmap<T>(grid: &Grid<T>, matches: &[WordMatch], length: usize) -> Grid<u32> {
+    let mut heat = grid.map(|_| 0);
+    for hit in matches {
+        for index in 0..length as i32 {
+            heat[(
+                hit.start.0 + hit.direction.0 * index,
+                hit.start.1 + hit.direction.1 * index,
+            )] += 1;
+        }
+    }
+    heat
+}
+
+/// How many X-MAS placements cover each cell. The centre `A` and the
+/// corners count, the cells between the corners don't.
+fn x_mas_heatmap<T>(grid: &Grid<T>, placements: &[Placement]) -> Grid<u32> {
+    let variants = x_mas::<u8>().variants(&Transform::ROTATIONS);
+    let mut heat = grid.map(|_| 0);
+    for placement in placements {
+        let (_, variant) = variants
+            .iter()
+            .find(|(transform, _)| *transform == placement.transform)
+            .unwrap();
+        for pos in variant.covered(placement.pos) {
+            heat[pos] += 1;
+        }
+    }
+    heat
+}
+
+/// Prints the grid with every covered letter in red, brighter the more
+/// matches cover it, followed by a legend of the counts.
+fn print_heatmap<T: Copy + Into<char>>(grid: &Grid<T>, heat: &Grid<u32>) {
+    let max = heat.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let shade = |count: u32| {
+        // never darker than a third of full red, so single hits stay visible
+        let red = 85 + (170 * count / max.max(1)) as u8;
+        Color::Rgb {
+            r: red,
+            g: red / 5,
+            b: red / 5,
+        }
+    };
+
+    for (y, row) in grid.rows().enumerate() {
+        for (x, letter) in row.iter().enumerate() {
+            let letter: char = (*letter).into();
+            match heat[(y as i32, x as i32)] {
+                0 => print!("{}", letter.dark_grey()),
+                count => print!("{}", letter.with(shade(count)).bold()),
+            }
+        }
+        println!();
+    }
+    print!("covered by:");
+    for count in 1..=max {
+        print!(" {}", count.to_string().with(shade(count)).bold());
+    }
+    println!();
 }
 
 fn task2() {
@@ -107,73 +267,26 @@ fn task2() {
 }
 
 fn solve2(input_lines: Vec<&str>) -> usize {
-    let input_lines: Vec<_> = input_lines
-        .into_iter()
-        .map(|string| string.chars().collect::<Vec<char>>())
-        .collect();
-
-    let res: usize = input_lines
-        .windows(3)
-        .map(|arr| {
-            arr[1]
-                .iter()
-                .enumerate()
-                .skip(1) // not checking leftmost
-                .take(arr[1].len() - 2) // not checking rightmost
-                .filter(|(index, char)| **char == 'A' && is_mas(*index, arr))
-                .count()
-        })
-        .sum();
-    res
-}
-
-fn is_mas(index: usize, arr: &[Vec<char>]) -> bool {
-    let chars = ['S', 'M'];
-    const INDEXES: [(usize, i32); 2] = [(0, -1), (2, 1)];
-    const INDEXES2: [(usize, i32); 2] = [(2, -1), (0, 1)];
-
-    let first = INDEXES
-        .iter()
-        .zip(chars.iter())
-        .all(|((y, x), char)| arr[*y][(index as i32 + *x) as usize] == *char);
-
-    let first_rev = INDEXES
-        .iter()
-        .zip(chars.iter().rev())
-        .all(|((y, x), char)| arr[*y][(index as i32 + *x) as usize] == *char);
-
-    let second = INDEXES2
-        .iter()
-        .zip(chars.iter())
-        .all(|((y, x), char)| arr[*y][(index as i32 + *x) as usize] == *char);
-
-    let second_rev = INDEXES2
-        .iter()
-        .zip(chars.iter().rev())
-        .all(|((y, x), char)| arr[*y][(index as i32 + *x) as usize] == *char);
-
-    (first || first_rev) && (second || second_rev)
-}
-
-fn is_match(
-    center: (usize, usize),
-    relative_coords: Vec<(i32, i32)>,
-    input_lines: &Vec<Vec<char>>,
-) -> Result<(), ()> {
-    let mas = ['M', 'A', 'S'];
-    for (index, coord) in relative_coords.iter().enumerate() {
-        let coord = (center.0 as i32 + coord.0, center.1 as i32 + coord.1);
-        let hit = get_char_in_coord(coord, input_lines)?;
-        if hit != mas[index] {
-            return Err(());
-        }
+    let input = input_lines.join("\n");
+    match ascii_grid(&input) {
+        Some(grid) => find_x_mas(&grid).len(),
+        None => find_x_mas(&Grid::parse(&input, |char| char)).len(),
     }
-    Ok(())
+}
+
+fn x_mas<T: From<u8>>() -> Pattern<T> {
+    Pattern::parse("M.S / .A. / M.S").map(|char| T::from(*char as u8))
+}
+
+fn find_x_mas<T: Letter + From<u8>>(grid: &Grid<T>) -> Vec<Placement> {
+    x_mas().find_all_in_bands(grid, &Transform::ROTATIONS, ROWS_PER_BAND)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{solve1, solve2};
+    use advent_of_code_2024::{grid::Grid, word_search::ascii_grid};
+
+    use crate::{find_x_mas, find_xmas, solve1, solve2, word_heatmap, x_mas_heatmap};
 
     #[test]
     fn test_task1() {
@@ -191,7 +304,8 @@ mod tests {
         ];
 
         let val = solve1(input);
-        println!("Res: {}", val)
+        println!("Res: {}", val);
+        assert_eq!(18, val);
     }
 
     #[test]
@@ -210,6 +324,33 @@ mod tests {
         ];
 
         let val = solve2(input);
-        println!("Res: {}", val)
+        println!("Res: {}", val);
+        assert_eq!(9, val);
+    }
+
+    #[test]
+    fn heatmaps() {
+        // matches touching every edge, including words read backwards into
+        // row and column 0
+        let grid = ascii_grid("SAMX\nAA..\nM.M.\nX..X").unwrap();
+        let matches = find_xmas(&grid);
+        assert_eq!(3, matches.len());
+        let heat = word_heatmap(&grid, &matches, 4);
+        assert_eq!(
+            vec![3, 1, 1, 1, 1, 1, 0, 0, 1, 0, 1, 0, 1, 0, 0, 1],
+            heat.iter().map(|(_, count)| *count).collect::<Vec<_>>()
+        );
+
+        let input = "M.S.M\n.A.A.\nM.S.M";
+        let grid = ascii_grid(input).unwrap();
+        let placements = find_x_mas(&grid);
+        // the char fallback places the X-MAS the same
+        assert_eq!(placements, find_x_mas(&Grid::parse(input, |char| char)));
+        assert_eq!(2, placements.len());
+        let heat = x_mas_heatmap(&grid, &placements);
+        assert_eq!(
+            vec![1, 0, 2, 0, 1, 0, 1, 0, 1, 0, 1, 0, 2, 0, 1],
+            heat.iter().map(|(_, count)| *count).collect::<Vec<_>>()
+        );
     }
 }
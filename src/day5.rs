@@ -1,48 +1,41 @@
-use std::slice::Iter;
+use std::{
+    collections::HashMap,
+    env,
+    fmt::{self, Display},
+};
 
-use advent_of_code_2024::task_argument;
+use advent_of_code_2024::{
+    graph::Graph,
+    parse::{sections, signed_integers},
+    task_argument,
+};
+use serde::Serialize;
 
 fn main() {
-    task_argument(task1, task2);
+    match env::args().nth(1).as_deref() {
+        Some("explain") => explain(env::args().skip(2).collect()),
+        _ => task_argument(task1, task2),
+    }
 }
 
 fn get_input() -> (Vec<Vec<i32>>, Vec<(i32, i32)>) {
-    let mut rules: Vec<String> = std::fs::read_to_string("inputs/day5.txt")
-        .unwrap()
-        .lines()
-        .map(|s| s.to_string())
-        .collect();
-
-    let spacer_index = rules
-        .iter()
-        .enumerate()
-        .find(|e| !e.1.contains("|"))
-        .unwrap()
-        .0;
+    parse_input(&std::fs::read_to_string("inputs/day5.txt").unwrap())
+}
 
-    let mut updates = rules.split_off(spacer_index);
-    updates.remove(0); // empty line between rules and updates
+fn parse_input(input: &str) -> (Vec<Vec<i32>>, Vec<(i32, i32)>) {
+    let [rules, updates] = sections(input)[..] else {
+        panic!("Expected rules and updates separated by an empty line");
+    };
 
     let rules: Vec<_> = rules
-        .into_iter()
-        .map(|input| {
-            let mut iterator = input.split("|");
-            (
-                iterator.next().unwrap().parse::<i32>().unwrap(),
-                iterator.next().unwrap().parse::<i32>().unwrap(),
-            )
+        .lines()
+        .map(|line| match signed_integers::<i32>(line)[..] {
+            [before, after] => (before, after),
+            _ => panic!("Expected a rule like 47|53, found {:?}", line),
         })
         .collect();
 
-    let updates: Vec<_> = updates
-        .into_iter()
-        .map(|input| {
-            input
-                .split(",")
-                .map(|val| val.parse::<i32>().unwrap())
-                .collect::<Vec<_>>()
-        })
-        .collect();
+    let updates: Vec<_> = updates.lines().map(signed_integers).collect();
     (updates, rules)
 }
 
@@ -53,93 +46,368 @@ fn task1() {
 }
 
 fn solve1(rules: Vec<(i32, i32)>, updates: Vec<Vec<i32>>) -> i32 {
+    let rules = Graph::from_edges(rules);
     updates
         .iter()
         .filter(|update| check_update(update, &rules))
-        .map(|update| update.get(update.len() / 2).unwrap())
+        .map(|update| update[update.len() / 2])
         .sum()
 }
 
-fn check_update(update: &[i32], rules: &[(i32, i32)]) -> bool {
-    update.iter().enumerate().all(|(index, item)| {
-        let to_check = &update[index + 1..];
-        to_check
-            .iter()
-            .all(|item2| !invalid_order(*item, *item2, rules))
-    })
-}
-
-fn invalid_order(a: i32, b: i32, rules: &[(i32, i32)]) -> bool {
-    rules.iter().any(|rule| {
-        rule.1 == a && rule.0 == b //the elements are in wrong places
+/// True if no rule puts a later page of the update before an earlier one.
+fn check_update(update: &[i32], rules: &Graph<i32>) -> bool {
+    let positions: HashMap<i32, usize> = update
+        .iter()
+        .enumerate()
+        .map(|(index, page)| (*page, index))
+        .collect();
+    update.iter().enumerate().all(|(index, page)| {
+        rules.successors(page).all(|after| {
+            positions
+                .get(after)
+                .is_none_or(|position| *position > index)
+        })
     })
 }
 
 fn task2() {
     let (updates, rules) = get_input();
-    let res = solve2(rules, updates);
-    println!("{}", res);
+    match solve2(rules, updates) {
+        Ok(res) => println!("{}", res),
+        Err(err) => eprintln!("{}", err),
+    }
 }
 
-fn solve2(rules: Vec<(i32, i32)>, mut updates: Vec<Vec<i32>>) -> i32 {
+fn solve2(rules: Vec<(i32, i32)>, updates: Vec<Vec<i32>>) -> Result<i32, RuleCycle> {
+    let rules = Graph::from_edges(rules);
     updates
-        .iter_mut()
-        .map(|update| {
-            let fixed = fix_update(update, &rules);
-            (fixed, update)
-        })
-        .filter(|(fixed, _)| *fixed)
-        .map(|(_, item)| item.get(item.len() / 2).unwrap())
+        .iter()
+        .filter(|update| !check_update(update, &rules))
+        .map(|update| fix_update(update, &rules).map(|fixed| fixed[fixed.len() / 2]))
         .sum()
 }
 
-fn fix_update(update: &mut [i32], rules: &[(i32, i32)]) -> bool {
-    let mut fixed = false;
-    for index in 0..(update.len()) {
-        while let Some((index2, _item2)) = update[index + 1..]
+/// The update's pages ordered by the rules between them. The full rule set
+/// may be cyclic, only the rules among the update's own pages have to agree.
+fn fix_update(update: &[i32], rules: &Graph<i32>) -> Result<Vec<i32>, RuleCycle> {
+    rules
+        .subgraph(update)
+        .topo_sort()
+        .map_err(|cycle| RuleCycle {
+            update: update.to_vec(),
+            cycle,
+        })
+}
+
+/// Rules among the pages of an update that contradict each other.
+#[derive(Debug, PartialEq)]
+struct RuleCycle {
+    update: Vec<i32>,
+    cycle: Vec<i32>,
+}
+
+impl Display for RuleCycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut cycle: Vec<_> = self.cycle.iter().map(i32::to_string).collect();
+        cycle.push(self.cycle[0].to_string());
+        write!(
+            f,
+            "Update {} cannot be ordered, its rules form a cycle: {}",
+            join(&self.update),
+            cycle.join(" -> ")
+        )
+    }
+}
+
+/// `day5 explain [--json] [FILE]`
+fn explain(args: Vec<String>) {
+    let mut file = "inputs/day5.txt".to_string();
+    let mut json = false;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            _ => file = arg,
+        }
+    }
+
+    let (updates, rules) = parse_input(&std::fs::read_to_string(file).unwrap());
+    let rules = Graph::from_edges(rules);
+    let mut explanations = Vec::new();
+    for update in &updates {
+        match explain_update(update, &rules) {
+            Ok(Some(explanation)) => explanations.push(explanation),
+            Ok(None) => (),
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&explanations).unwrap());
+    } else {
+        explanations
             .iter()
-            .enumerate()
-            .find(|(_, item2)| invalid_order(update[index], **item2, rules))
-        {
-            fixed = true;
-            let actual_index2 = index + 1 + index2;
-            update.swap(index, actual_index2);
+            .for_each(|explanation| print!("{}", explanation));
+        println!("{} invalid updates", explanations.len());
+    }
+}
+
+/// Why an update is out of order and how it was fixed.
+#[derive(Debug, PartialEq, Serialize)]
+struct Explanation {
+    update: Vec<i32>,
+    violations: Vec<Violation>,
+    moves: Vec<PageMove>,
+    fixed: Vec<i32>,
+    middle: i32,
+}
+
+/// A rule `before|after` that the update breaks, with where each page is.
+#[derive(Debug, PartialEq, Serialize)]
+struct Violation {
+    before: i32,
+    after: i32,
+    before_position: usize,
+    after_position: usize,
+}
+
+/// A page taken out of the update and put back in. `from` is its index in the
+/// update and `to` its index in the fixed order, so they can be equal.
+#[derive(Debug, PartialEq, Serialize)]
+struct PageMove {
+    page: i32,
+    from: usize,
+    to: usize,
+}
+
+/// `None` if the update is already in order.
+fn explain_update(update: &[i32], rules: &Graph<i32>) -> Result<Option<Explanation>, RuleCycle> {
+    let positions: HashMap<i32, usize> = update
+        .iter()
+        .enumerate()
+        .map(|(index, page)| (*page, index))
+        .collect();
+    let positions = &positions;
+    let mut violations: Vec<_> = update
+        .iter()
+        .enumerate()
+        .flat_map(|(index, page)| {
+            rules.successors(page).filter_map(move |after| {
+                let after_position = *positions.get(after)?;
+                (after_position < index).then_some(Violation {
+                    before: *page,
+                    after: *after,
+                    before_position: index,
+                    after_position,
+                })
+            })
+        })
+        .collect();
+    if violations.is_empty() {
+        return Ok(None);
+    }
+    violations.sort_by_key(|violation| (violation.after_position, violation.before_position));
+
+    let fixed = fix_update(update, rules)?;
+    let fixed_positions: HashMap<i32, usize> = fixed
+        .iter()
+        .enumerate()
+        .map(|(index, page)| (*page, index))
+        .collect();
+    let targets: Vec<_> = update.iter().map(|page| fixed_positions[page]).collect();
+    // pages already in the right relative order can stay, everything else moves
+    let stay = longest_increasing(&targets);
+    let mut moves: Vec<_> = (0..update.len())
+        .filter(|index| !stay.contains(index))
+        .map(|index| PageMove {
+            page: update[index],
+            from: index,
+            to: targets[index],
+        })
+        .collect();
+    moves.sort_by_key(|page_move| page_move.to);
+
+    Ok(Some(Explanation {
+        update: update.to_vec(),
+        violations,
+        moves,
+        middle: fixed[fixed.len() / 2],
+        fixed,
+    }))
+}
+
+/// Indices of a longest strictly increasing subsequence, in order.
+fn longest_increasing(values: &[usize]) -> Vec<usize> {
+    // tails[len] is the index ending the best subsequence of length len + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut parents: Vec<Option<usize>> = vec![None; values.len()];
+    for (index, value) in values.iter().enumerate() {
+        let len = tails.partition_point(|&tail| values[tail] < *value);
+        parents[index] = len.checked_sub(1).map(|previous| tails[previous]);
+        if len == tails.len() {
+            tails.push(index);
+        } else {
+            tails[len] = index;
+        }
+    }
+
+    let mut indices = Vec::new();
+    let mut current = tails.last().copied();
+    while let Some(index) = current {
+        indices.push(index);
+        current = parents[index];
+    }
+    indices.reverse();
+    indices
+}
+
+fn join(pages: &[i32]) -> String {
+    let pages: Vec<_> = pages.iter().map(i32::to_string).collect();
+    pages.join(",")
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Update {}", join(&self.update))?;
+        for violation in &self.violations {
+            writeln!(
+                f,
+                "  breaks {}|{}: {} is at {} but {} is at {}",
+                violation.before,
+                violation.after,
+                violation.before,
+                violation.before_position,
+                violation.after,
+                violation.after_position
+            )?;
+        }
+        for page_move in &self.moves {
+            writeln!(
+                f,
+                "  move {} from {} to {}",
+                page_move.page, page_move.from, page_move.to
+            )?;
         }
+        writeln!(
+            f,
+            "  fixed {}, middle page {}",
+            join(&self.fixed),
+            self.middle
+        )
     }
-    fixed
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{check_update, fix_update, solve1, solve2};
+    use advent_of_code_2024::graph::Graph;
+
+    use crate::{
+        check_update, explain_update, fix_update, longest_increasing, solve1, solve2, PageMove,
+        RuleCycle, Violation,
+    };
 
     #[test]
     fn test_task1() {
         let (rules, updates) = get_data();
 
-        updates.clone().into_iter().for_each(|update| {
-            let res1 = check_update(&update, &rules);
-            println!("res {}", res1);
-        });
+        let graph = Graph::from_edges(rules.clone());
+        let checked: Vec<_> = updates
+            .iter()
+            .map(|update| check_update(update, &graph))
+            .collect();
+        assert_eq!(vec![true, true, true, false, false, false], checked);
 
-        let score = solve1(rules.clone(), updates);
-        println!("score: {}", score);
+        assert_eq!(143, solve1(rules, updates));
     }
 
     #[test]
     fn test_task2() {
         let (rules, updates) = get_data();
-        let score = solve2(rules, updates);
-        println!("score: {}", score);
+        assert_eq!(Ok(123), solve2(rules, updates));
     }
 
     #[test]
     fn fix2() {
-        let (rules, mut updates) = get_data();
-        updates.iter_mut().for_each(|item| {
-            let fixed = fix_update(item, &rules);
-            println!("Fixed: {:?} {}", item, fixed)
-        });
+        let (rules, updates) = get_data();
+        let graph = Graph::from_edges(rules);
+        let fixed: Vec<_> = updates[3..]
+            .iter()
+            .map(|update| fix_update(update, &graph).unwrap())
+            .collect();
+        assert_eq!(
+            vec![
+                vec![97, 75, 47, 61, 53],
+                vec![61, 29, 13],
+                vec![97, 75, 47, 29, 13]
+            ],
+            fixed
+        );
+    }
+
+    #[test]
+    fn cyclic_rules() {
+        // the cycle only matters to updates containing all three pages
+        let rules = vec![(1, 2), (2, 3), (3, 1)];
+        assert_eq!(Ok(2), solve2(rules.clone(), vec![vec![2, 1]]));
+
+        let err = solve2(rules, vec![vec![1, 2], vec![3, 2, 1]]).unwrap_err();
+        assert_eq!(
+            RuleCycle {
+                update: vec![3, 2, 1],
+                cycle: vec![1, 2, 3]
+            },
+            err
+        );
+        assert_eq!(
+            "Update 3,2,1 cannot be ordered, its rules form a cycle: 1 -> 2 -> 3 -> 1",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn explain() {
+        let (rules, updates) = get_data();
+        let graph = Graph::from_edges(rules);
+        assert_eq!(None, explain_update(&updates[0], &graph).unwrap());
+
+        let explanation = explain_update(&updates[3], &graph).unwrap().unwrap();
+        assert_eq!(
+            vec![Violation {
+                before: 97,
+                after: 75,
+                before_position: 1,
+                after_position: 0
+            }],
+            explanation.violations
+        );
+        assert_eq!(
+            vec![PageMove {
+                page: 75,
+                from: 0,
+                to: 1
+            }],
+            explanation.moves
+        );
+        assert_eq!(47, explanation.middle);
+        assert_eq!(
+            "Update 75,97,47,61,53
+  breaks 97|75: 97 is at 1 but 75 is at 0
+  move 75 from 0 to 1
+  fixed 97,75,47,61,53, middle page 47
+",
+            explanation.to_string()
+        );
+
+        // 97,13,75,29,47 -> 97,75,47,29,13 keeps 97,75,47 and moves 29 and 13
+        let explanation = explain_update(&updates[5], &graph).unwrap().unwrap();
+        assert_eq!(4, explanation.violations.len());
+        let moved: Vec<_> = explanation.moves.iter().map(|m| m.page).collect();
+        assert_eq!(vec![29, 13], moved);
+    }
+
+    #[test]
+    fn increasing() {
+        assert_eq!(vec![1, 2, 3], longest_increasing(&[1, 0, 2, 4]));
+        assert_eq!(Vec::<usize>::new(), longest_increasing(&[]));
     }
 
     fn get_data() -> (Vec<(i32, i32)>, Vec<Vec<i32>>) {
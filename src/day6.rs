@@ -1,6 +1,12 @@
 use std::time::Instant;
 
-use advent_of_code_2024::task_argument_with_input;
+use advent_of_code_2024::{
+    bitgrid::BitGrid,
+    grid::Grid,
+    search::walk_loops,
+    simulation::{Driver, Simulation},
+    task_argument_with_input,
+};
 use nom::InputIter;
 use rayon::prelude::*;
 
@@ -13,13 +19,10 @@ fn main() {
 fn task1(input: String) -> String {
     let input: Vec<_> = input.lines().collect();
     let walls = get_walls(&input);
-    let guard = guard_pos(&input);
-    let direction = Direction::Up;
-    let steps = get_steps(guard, direction, &walls);
-    let mut steps_coords: Vec<_> = steps.into_iter().map(|e| e.0).collect();
-    steps_coords.sort();
-    steps_coords.dedup();
-    steps_coords.len().to_string()
+    let guard = Guard::new(guard_pos(&input), &walls);
+    let mut driver = Driver::new(guard);
+    driver.run_to_end();
+    driver.simulation().visited.count().to_string()
 }
 
 fn task2(input: String) -> String {
@@ -29,35 +32,98 @@ fn task2(input: String) -> String {
     let direction = Direction::Up;
     let steps = get_steps(guard, direction, &walls);
 
-    let steps_clone = steps.clone();
-    let possible_walls: Vec<_> = steps
+    // a wall can only be placed where the guard first walks, earlier steps would change the path
+    let mut visited = BitGrid::new(walls.height(), walls.width());
+    visited.insert(steps[0].0);
+    let first_visits: Vec<_> = (1..steps.len())
+        .filter(|index| visited.insert(steps[*index].0))
+        .collect();
+
+    let possible_walls: Vec<_> = first_visits
         .into_par_iter()
-        .enumerate()
-        .skip(1)
-        .filter(|(index, pos_and_dir)| {
-            let already_walked = &steps_clone[..*index].iter().find(|e| e.0 == pos_and_dir.0);
-            let start = steps_clone[index - 1].0;
-            already_walked.is_none()
-                && !can_exit(start, pos_and_dir.1.clone(), &walls, pos_and_dir.0)
+        .filter(|index| {
+            let (pos, direction) = &steps[*index];
+            let start = steps[index - 1].0;
+            !can_exit(start, direction.clone(), &walls, *pos)
         })
-        .map(|e| e.1 .0)
+        .map(|index| steps[index].0)
         .collect();
 
     possible_walls.len().to_string()
 }
 
+/// The guard walking one cell, or turning, per step until it leaves the map.
+struct Guard<'a> {
+    pos: (i32, i32),
+    direction: Direction,
+    walls: &'a BitGrid,
+    visited: BitGrid,
+    left: bool,
+}
+
+impl<'a> Guard<'a> {
+    fn new(pos: (i32, i32), walls: &'a BitGrid) -> Self {
+        let mut visited = BitGrid::new(walls.height(), walls.width());
+        visited.insert(pos);
+        Self {
+            pos,
+            direction: Direction::Up,
+            walls,
+            visited,
+            left: false,
+        }
+    }
+}
+
+impl Simulation for Guard<'_> {
+    type State = ((i32, i32), Direction);
+
+    fn step(&mut self) {
+        let vector = self.direction.vector();
+        let next_step = (self.pos.0 + vector.0, self.pos.1 + vector.1);
+        if out_of_bounds(next_step, self.walls) {
+            self.left = true;
+        } else if is_wall(next_step, self.walls) {
+            self.direction = self.direction.next();
+        } else {
+            self.pos = next_step;
+            self.visited.insert(next_step);
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.left
+    }
+
+    fn render(&self) -> Grid<char> {
+        let mut grid = Grid::new(self.walls.height(), self.walls.width(), '.');
+        self.walls.iter().for_each(|pos| grid[pos] = '#');
+        self.visited.iter().for_each(|pos| grid[pos] = 'X');
+        if !self.left {
+            grid[self.pos] = match self.direction {
+                Direction::Up => '^',
+                Direction::Right => '>',
+                Direction::Down => 'v',
+                Direction::Left => '<',
+            };
+        }
+        grid
+    }
+
+    fn state(&self) -> Self::State {
+        (self.pos, self.direction.clone())
+    }
+}
+
 fn get_steps(
     mut start: (i32, i32),
     mut direction: Direction,
-    walls: &Vec<Vec<bool>>,
+    walls: &BitGrid,
 ) -> Vec<((i32, i32), Direction)> {
     let mut steps: Vec<((i32, i32), Direction)> = Vec::new();
     //first pos needs to be included by the spec
     steps.push((start, direction.clone()));
-    loop {
-        let Ok(pos) = next_stop(start, &direction, &walls, &mut steps, None) else {
-            break;
-        };
+    while let Ok(pos) = next_stop(start, &direction, walls, &mut steps, None) {
         start = pos;
         direction = direction.next();
     }
@@ -65,40 +131,31 @@ fn get_steps(
 }
 
 fn can_exit(
-    mut guard: (i32, i32),
-    mut direction: Direction,
-    walls: &[Vec<bool>],
+    guard: (i32, i32),
+    direction: Direction,
+    walls: &BitGrid,
     extra_wall: (i32, i32),
 ) -> bool {
-    let mut visited_stops: Vec<((i32, i32), Direction)> = Vec::new();
-    loop {
-        let mut steps_to_obstacle = Vec::new();
-        let Ok(pos) = next_stop(
-            guard,
-            &direction,
-            walls,
-            &mut steps_to_obstacle,
-            Some(extra_wall),
-        ) else {
-            break;
-        };
-
-        let stop = (pos, direction.clone());
-        if visited_stops.contains(&stop) {
-            return false; // in a loop!
-        }
-        visited_stops.push(stop);
-
-        guard = pos;
-        direction = direction.next();
-    }
-    true
+    // each state is a stop and the direction the guard turns to there, kept
+    // in one bitset per direction
+    let mut stops: [BitGrid; 4] =
+        std::array::from_fn(|_| BitGrid::new(walls.height(), walls.width()));
+    let in_a_loop = walk_loops(
+        (guard, direction),
+        |(pos, direction)| {
+            next_stop(*pos, direction, walls, &mut Vec::new(), Some(extra_wall))
+                .ok()
+                .map(|pos| (pos, direction.next()))
+        },
+        |(pos, direction)| stops[direction.index()].insert(*pos),
+    );
+    !in_a_loop
 }
 
 fn next_stop(
     mut guard: (i32, i32),
     direction: &Direction,
-    walls: &[Vec<bool>],
+    walls: &BitGrid,
     steps: &mut Vec<((i32, i32), Direction)>,
     extra_wall: Option<(i32, i32)>,
 ) -> Result<(i32, i32), (i32, i32)> {
@@ -119,18 +176,15 @@ fn next_stop(
     }
 }
 
-fn is_wall(pos: (i32, i32), walls: &[Vec<bool>]) -> bool {
-    walls[pos.0 as usize][pos.1 as usize]
+fn is_wall(pos: (i32, i32), walls: &BitGrid) -> bool {
+    walls.contains(pos)
 }
 
-fn out_of_bounds(next_step: (i32, i32), walls: &[Vec<bool>]) -> bool {
-    next_step.0 < 0
-        || next_step.1 < 0
-        || next_step.0 >= walls.len() as i32
-        || next_step.1 >= walls.first().unwrap().len() as i32
+fn out_of_bounds(next_step: (i32, i32), walls: &BitGrid) -> bool {
+    !walls.in_bounds(next_step)
 }
 
-fn guard_pos(input: &Vec<&str>) -> (i32, i32) {
+fn guard_pos(input: &[&str]) -> (i32, i32) {
     for (y, line) in input.iter().enumerate() {
         if let Some((x, _)) = line.iter_indices().find(|(_, char)| *char == '^') {
             return (y as i32, x as i32);
@@ -139,16 +193,8 @@ fn guard_pos(input: &Vec<&str>) -> (i32, i32) {
     panic!("No guard pos")
 }
 
-fn get_walls(input: &Vec<&str>) -> Vec<Vec<bool>> {
-    let walls: Vec<_> = input
-        .iter()
-        .map(|e| {
-            e.iter_elements()
-                .map(|char| char == '#')
-                .collect::<Vec<_>>()
-        })
-        .collect();
-    walls
+fn get_walls(input: &[&str]) -> BitGrid {
+    BitGrid::parse(&input.join("\n"), |char| char == '#')
 }
 
 #[derive(PartialEq, Eq, Hash, Clone)]
@@ -177,11 +223,22 @@ impl Direction {
             Direction::Left => Direction::Up,
         }
     }
+
+    pub fn index(&self) -> usize {
+        match self {
+            Direction::Up => 0,
+            Direction::Right => 1,
+            Direction::Down => 2,
+            Direction::Left => 3,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{get_walls, is_wall, task1, task2};
+    use advent_of_code_2024::simulation::{Driver, Simulation};
+
+    use crate::{get_walls, guard_pos, is_wall, task1, task2, Guard};
 
     const TEST_INPUT: &str = "....#.....
 .........#
@@ -198,12 +255,14 @@ mod tests {
     fn task1_test() {
         let count = task1(TEST_INPUT.to_string());
         println!("count: {}", count);
+        assert_eq!("41", count);
     }
 
     #[test]
     fn task2_test() {
         let count = task2(TEST_INPUT.to_string());
         println!("count: {}", count);
+        assert_eq!("6", count);
     }
 
     #[test]
@@ -213,4 +272,16 @@ mod tests {
 
         assert!(is_wall((0, 4), &walls));
     }
+
+    #[test]
+    fn guard_render_test() {
+        let input: Vec<_> = TEST_INPUT.lines().collect();
+        let walls = get_walls(&input);
+        let mut driver = Driver::new(Guard::new(guard_pos(&input), &walls));
+        driver.run(6);
+        let frame = driver.simulation().render().to_string();
+        assert_eq!("....#.....", frame.lines().next().unwrap());
+        assert_eq!("....>....#", frame.lines().nth(1).unwrap());
+        assert_eq!(".#..X.....", frame.lines().nth(6).unwrap());
+    }
 }
@@ -1,6 +1,8 @@
 use std::time::Instant;
 
-use advent_of_code_2024::task_argument_with_input;
+use advent_of_code_2024::{
+    math::checked_concat, parse::unsigned_integers, search::dfs, task_argument_with_input,
+};
 
 fn main() {
     let time = Instant::now();
@@ -10,50 +12,36 @@ fn main() {
 
 fn task1(input: String) -> String {
     let problems = parse_input(input);
-
-    let mut total = 0;
-    for (answer, mut members) in problems {
-        let mut accs: Vec<u64> = vec![members.remove(0)];
-
-        for member in members {
-            accs = accs
-                .iter()
-                .flat_map(|acc| vec![acc * member, acc + member])
-                .collect()
-        }
-        if accs.contains(&answer) {
-            total += answer;
-        }
-    }
-
-    total.to_string()
+    let operators = &[Operator::Add, Operator::Multiply];
+    solve(&problems, operators).to_string()
 }
 
 fn task2(input: String) -> String {
     let problems = parse_input(input);
     let operators = &[Operator::Add, Operator::Multiply, Operator::Concat];
+    solve(&problems, operators).to_string()
+}
+
+fn solve(problems: &[(u64, Vec<u64>)], operators: &[Operator]) -> u64 {
     problems
         .iter()
-        .filter(|(goal, members)| has_solution2(operators, *goal, members[0], &members[1..]))
+        .filter(|(goal, members)| has_solution(operators, *goal, members))
         .map(|c| c.0)
-        .sum::<u64>()
-        .to_string()
+        .sum()
 }
 
-fn has_solution2(operators: &[Operator], goal: u64, current: u64, rest: &[u64]) -> bool {
-    if rest.is_empty() {
-        return goal == current;
-    }
-
-    if current > goal {
-        return false;
-    }
-
-    let first = rest.first().unwrap();
-
-    operators
-        .iter()
-        .any(|op| has_solution2(operators, goal, op.calc(current, *first), &rest[1..]))
+fn has_solution(operators: &[Operator], goal: u64, members: &[u64]) -> bool {
+    // state is the value so far and how many members went into it
+    let successors = |&(current, used): &(u64, usize)| {
+        operators
+            .iter()
+            .filter(move |_| used < members.len())
+            .filter_map(move |op| op.calc(current, members[used]))
+            .filter(|value| *value <= goal)
+            .map(move |value| (value, used + 1))
+    };
+    let is_goal = |&(current, used): &(u64, usize)| used == members.len() && current == goal;
+    dfs((members[0], 1), successors, is_goal).is_some()
 }
 
 enum Operator {
@@ -63,11 +51,12 @@ enum Operator {
 }
 
 impl Operator {
-    fn calc(&self, a: u64, b: u64) -> u64 {
+    /// `None` when the result overflows, which can never reach the goal anyway.
+    fn calc(&self, a: u64, b: u64) -> Option<u64> {
         match self {
-            Operator::Add => a + b,
-            Operator::Multiply => a * b,
-            Operator::Concat => concat(a, b),
+            Operator::Add => a.checked_add(b),
+            Operator::Multiply => a.checked_mul(b),
+            Operator::Concat => checked_concat(a, b),
         }
     }
 }
@@ -76,32 +65,35 @@ fn parse_input(input: String) -> Vec<(u64, Vec<u64>)> {
     let problems: Vec<_> = input
         .lines()
         .map(|line| {
-            let (answer, rest) = line.split_once(":").unwrap();
-            let answer = answer.parse::<u64>().unwrap();
-            let members: Vec<_> = rest
-                .split_ascii_whitespace()
-                .map(|x| x.parse::<u64>().unwrap())
-                .collect();
+            let mut members = unsigned_integers::<u64>(line);
+            let answer = members.remove(0);
             (answer, members)
         })
         .collect();
     problems
 }
 
-fn concat(num1: u64, num2: u64) -> u64 {
-    let num2_len = count_digits(num2);
-    let multiplier = 10_u64.pow(num2_len);
-    num1 * multiplier + num2
-}
-
-fn count_digits(mut n: u64) -> u32 {
-    if n == 0 {
-        return 1;
+#[cfg(test)]
+mod tests {
+    use crate::{task1, task2};
+
+    const INPUT: &str = "190: 10 19
+3267: 81 40 27
+83: 17 5
+156: 15 6
+7290: 6 8 6 15
+161011: 16 10 13
+192: 17 8 14
+21037: 9 7 18 13
+292: 11 6 16 20";
+
+    #[test]
+    fn test_task1() {
+        assert_eq!("3749", task1(INPUT.to_string()));
     }
-    let mut count = 0;
-    while n != 0 {
-        n /= 10;
-        count += 1;
+
+    #[test]
+    fn test_task2() {
+        assert_eq!("11387", task2(INPUT.to_string()));
     }
-    count
 }
@@ -3,7 +3,7 @@ use std::{
     time::Instant,
 };
 
-use advent_of_code_2024::task_argument_with_input;
+use advent_of_code_2024::{bitgrid::BitGrid, math::gcd, task_argument_with_input};
 use nom::InputIter;
 
 fn main() {
@@ -18,7 +18,7 @@ fn task1(input: String) -> String {
         mut antinodes,
     } = parse_input(input);
 
-    antennas.iter().for_each(|(char, list)| {
+    antennas.iter().for_each(|(_, list)| {
         list.iter().for_each(|coords| {
             list.iter().for_each(|coords2| {
                 if coords != coords2 {
@@ -32,23 +32,11 @@ fn task1(input: String) -> String {
         })
     });
 
-    let res: usize = antinodes
-        .iter()
-        .map(|line| line.iter().filter(|e| **e).count())
-        .sum();
-    res.to_string()
+    antinodes.count().to_string()
 }
 
-fn add_antinode(antinodes: &mut [Vec<bool>], location: (i32, i32)) -> bool {
-    if location.0 < 0
-        || location.1 < 0
-        || location.0 >= antinodes.len() as i32
-        || location.1 >= antinodes.first().unwrap().len() as i32
-    {
-        return false;
-    }
-    antinodes[location.0 as usize][location.1 as usize] = true;
-    true
+fn add_antinode(antinodes: &mut BitGrid, location: (i32, i32)) -> bool {
+    antinodes.set(location, true)
 }
 
 fn task2(input: String) -> String {
@@ -61,39 +49,27 @@ fn task2(input: String) -> String {
         list.iter().for_each(|coords| {
             list.iter().for_each(|coords2| {
                 if coords != coords2 {
-                    add_antinode(&mut antinodes, *coords);
-                    add_antinode(&mut antinodes, *coords2);
+                    // every grid point on the line counts, so step by the smallest whole delta
                     let dist = (coords.0 - coords2.0, coords.1 - coords2.1);
-                    let mut pos = (coords.0 + dist.0, coords.1 + dist.1);
-                    loop {
-                        if !add_antinode(&mut antinodes, pos) {
-                            break;
-                        }
-                        pos = (pos.0 + dist.0, pos.1 + dist.1);
-                    }
-
-                    let mut pos = (coords2.0 - dist.0, coords2.1 - dist.1);
-                    loop {
-                        if !add_antinode(&mut antinodes, pos) {
-                            break;
+                    let divisor = gcd(dist.0 as i64, dist.1 as i64) as i32;
+                    let step = (dist.0 / divisor, dist.1 / divisor);
+                    for step in [step, (-step.0, -step.1)] {
+                        let mut pos = *coords;
+                        while add_antinode(&mut antinodes, pos) {
+                            pos = (pos.0 + step.0, pos.1 + step.1);
                         }
-                        pos = (pos.0 - dist.0, pos.1 - dist.1);
                     }
                 }
             })
         })
     });
 
-    let res: usize = antinodes
-        .iter()
-        .map(|line| line.iter().filter(|e| **e).count())
-        .sum();
-    res.to_string()
+    antinodes.count().to_string()
 }
 
 pub struct Input {
     antennas: HashMap<char, Vec<(i32, i32)>>,
-    antinodes: Vec<Vec<bool>>,
+    antinodes: BitGrid,
 }
 
 fn parse_input(input: String) -> Input {
@@ -113,9 +89,37 @@ fn parse_input(input: String) -> Input {
         })
     });
 
-    let antinodes = vec![vec![false; width]; height];
+    let antinodes = BitGrid::new(height, width);
     Input {
         antennas,
         antinodes,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{task1, task2};
+
+    const INPUT: &str = "............
+........0...
+.....0......
+.......0....
+....0.......
+......A.....
+............
+............
+........A...
+.........A..
+............
+............";
+
+    #[test]
+    fn test_task1() {
+        assert_eq!("14", task1(INPUT.to_string()));
+    }
+
+    #[test]
+    fn test_task2() {
+        assert_eq!("34", task2(INPUT.to_string()));
+    }
+}
@@ -0,0 +1,403 @@
+//! Directed graphs over hashable nodes.
+//!
+//! Nodes are interned to dense ids on insertion, so the algorithms work on
+//! plain adjacency lists and only translate back to nodes in their results.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::{Display, Write},
+    hash::Hash,
+};
+
+use crate::search;
+
+#[derive(Clone, Debug)]
+pub struct Graph<N> {
+    nodes: Vec<N>,
+    ids: HashMap<N, usize>,
+    outgoing: Vec<Vec<usize>>,
+    incoming: Vec<Vec<usize>>,
+    /// Every edge as a pair of ids, to reject duplicates.
+    edge_set: HashSet<(usize, usize)>,
+}
+
+impl<N: Hash + Eq + Clone> Default for Graph<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: Hash + Eq + Clone> Graph<N> {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+            outgoing: Vec::new(),
+            incoming: Vec::new(),
+            edge_set: HashSet::new(),
+        }
+    }
+
+    pub fn from_edges(edges: impl IntoIterator<Item = (N, N)>) -> Self {
+        let mut graph = Self::new();
+        for (from, to) in edges {
+            graph.add_edge(from, to);
+        }
+        graph
+    }
+
+    /// Adds `node` if it is new. Returns its id either way.
+    pub fn add_node(&mut self, node: N) -> usize {
+        if let Some(&id) = self.ids.get(&node) {
+            return id;
+        }
+        let id = self.nodes.len();
+        self.ids.insert(node.clone(), id);
+        self.nodes.push(node);
+        self.outgoing.push(Vec::new());
+        self.incoming.push(Vec::new());
+        id
+    }
+
+    /// Adds both nodes if needed. Returns false if the edge already existed.
+    pub fn add_edge(&mut self, from: N, to: N) -> bool {
+        let from = self.add_node(from);
+        let to = self.add_node(to);
+        self.link(from, to)
+    }
+
+    fn link(&mut self, from: usize, to: usize) -> bool {
+        if !self.edge_set.insert((from, to)) {
+            return false;
+        }
+        self.outgoing[from].push(to);
+        self.incoming[to].push(from);
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edge_set.len()
+    }
+
+    /// Nodes in insertion order.
+    pub fn nodes(&self) -> &[N] {
+        &self.nodes
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (&N, &N)> {
+        self.outgoing
+            .iter()
+            .enumerate()
+            .flat_map(move |(from, tos)| {
+                tos.iter()
+                    .map(move |&to| (&self.nodes[from], &self.nodes[to]))
+            })
+    }
+
+    pub fn id(&self, node: &N) -> Option<usize> {
+        self.ids.get(node).copied()
+    }
+
+    pub fn contains(&self, node: &N) -> bool {
+        self.ids.contains_key(node)
+    }
+
+    pub fn has_edge(&self, from: &N, to: &N) -> bool {
+        match (self.id(from), self.id(to)) {
+            (Some(from), Some(to)) => self.edge_set.contains(&(from, to)),
+            _ => false,
+        }
+    }
+
+    pub fn successors<'a>(&'a self, node: &N) -> impl Iterator<Item = &'a N> {
+        self.neighbours(node, &self.outgoing)
+    }
+
+    pub fn predecessors<'a>(&'a self, node: &N) -> impl Iterator<Item = &'a N> {
+        self.neighbours(node, &self.incoming)
+    }
+
+    fn neighbours<'a>(&'a self, node: &N, lists: &'a [Vec<usize>]) -> impl Iterator<Item = &'a N> {
+        self.id(node)
+            .into_iter()
+            .flat_map(move |id| lists[id].iter().map(|&other| &self.nodes[other]))
+    }
+
+    /// The graph restricted to the given nodes and the edges between them.
+    /// Nodes not in the graph are added without edges.
+    pub fn subgraph<'a>(&self, nodes: impl IntoIterator<Item = &'a N>) -> Self
+    where
+        N: 'a,
+    {
+        let mut subgraph = Self::new();
+        for node in nodes {
+            subgraph.add_node(node.clone());
+        }
+        for id in 0..subgraph.len() {
+            let Some(original) = self.id(&subgraph.nodes[id]) else {
+                continue;
+            };
+            for &to in &self.outgoing[original] {
+                if let Some(&to) = subgraph.ids.get(&self.nodes[to]) {
+                    subgraph.link(id, to);
+                }
+            }
+        }
+        subgraph
+    }
+
+    /// Every node reachable from `start`, including `start` itself.
+    pub fn reachable(&self, start: &N) -> HashSet<N> {
+        let Some(start) = self.id(start) else {
+            return HashSet::new();
+        };
+        search::reachable([start], |&id| self.outgoing[id].clone())
+            .into_iter()
+            .map(|id| self.nodes[id].clone())
+            .collect()
+    }
+
+    /// Orders the nodes so every edge points forward, using Kahn's algorithm.
+    /// Ties keep insertion order. If the graph has a cycle, returns the nodes
+    /// of one cycle instead, each with an edge to the next and the last back
+    /// to the first.
+    pub fn topo_sort(&self) -> Result<Vec<N>, Vec<N>> {
+        let mut in_degree: Vec<usize> = self.incoming.iter().map(Vec::len).collect();
+        let mut ready: VecDeque<usize> = (0..self.len()).filter(|&id| in_degree[id] == 0).collect();
+        let mut order = Vec::with_capacity(self.len());
+        while let Some(id) = ready.pop_front() {
+            order.push(self.nodes[id].clone());
+            for &to in &self.outgoing[id] {
+                in_degree[to] -= 1;
+                if in_degree[to] == 0 {
+                    ready.push_back(to);
+                }
+            }
+        }
+        if order.len() == self.len() {
+            return Ok(order);
+        }
+
+        // every node left over still has a predecessor that is left over, so
+        // walking backwards through them has to come back around
+        let start = (0..self.len()).find(|&id| in_degree[id] > 0).unwrap();
+        let mut position = HashMap::new();
+        let mut walked = Vec::new();
+        let mut id = start;
+        while !position.contains_key(&id) {
+            position.insert(id, walked.len());
+            walked.push(id);
+            id = *self.incoming[id]
+                .iter()
+                .find(|&&from| in_degree[from] > 0)
+                .unwrap();
+        }
+        let cycle = walked[position[&id]..]
+            .iter()
+            .rev()
+            .map(|&id| self.nodes[id].clone())
+            .collect();
+        Err(cycle)
+    }
+
+    pub fn find_cycle(&self) -> Option<Vec<N>> {
+        self.topo_sort().err()
+    }
+
+    /// Strongly connected components using Tarjan's algorithm, in reverse
+    /// topological order: no component has an edge to a later one.
+    pub fn sccs(&self) -> Vec<Vec<N>> {
+        let mut tarjan = Tarjan {
+            outgoing: &self.outgoing,
+            index: vec![None; self.len()],
+            low: vec![0; self.len()],
+            on_stack: vec![false; self.len()],
+            stack: Vec::new(),
+            next_index: 0,
+            components: Vec::new(),
+        };
+        for id in 0..self.len() {
+            if tarjan.index[id].is_none() {
+                tarjan.visit(id);
+            }
+        }
+        tarjan
+            .components
+            .into_iter()
+            .map(|ids| ids.into_iter().map(|id| self.nodes[id].clone()).collect())
+            .collect()
+    }
+}
+
+struct Tarjan<'a> {
+    outgoing: &'a [Vec<usize>],
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next_index: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    /// Depth-first search from `root` with an explicit path, each node on it
+    /// paired with the next of its edges to follow, so long chains can't
+    /// overflow the call stack.
+    fn visit(&mut self, root: usize) {
+        self.open(root);
+        let mut path = vec![(root, 0)];
+        while let Some((id, next)) = path.last_mut() {
+            let id = *id;
+            if let Some(&to) = self.outgoing[id].get(*next) {
+                *next += 1;
+                match self.index[to] {
+                    None => {
+                        self.open(to);
+                        path.push((to, 0));
+                    }
+                    Some(index) if self.on_stack[to] => self.low[id] = self.low[id].min(index),
+                    Some(_) => (),
+                }
+                continue;
+            }
+
+            path.pop();
+            if let Some(&(parent, _)) = path.last() {
+                self.low[parent] = self.low[parent].min(self.low[id]);
+            }
+            self.close(id);
+        }
+    }
+
+    fn open(&mut self, id: usize) {
+        self.index[id] = Some(self.next_index);
+        self.low[id] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(id);
+        self.on_stack[id] = true;
+    }
+
+    /// Pops the component `id` is the root of, if it is one, once all its
+    /// edges have been followed.
+    fn close(&mut self, id: usize) {
+        if Some(self.low[id]) == self.index[id] {
+            let mut component = Vec::new();
+            loop {
+                let member = self.stack.pop().unwrap();
+                self.on_stack[member] = false;
+                component.push(member);
+                if member == id {
+                    break;
+                }
+            }
+            component.reverse();
+            self.components.push(component);
+        }
+    }
+}
+
+impl<N: Hash + Eq + Clone + Display> Graph<N> {
+    /// Graphviz source for the graph, with every node and edge on its own line.
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = format!("digraph \"{}\" {{\n", escape(name));
+        for node in &self.nodes {
+            writeln!(dot, "    \"{}\";", escape(node)).unwrap();
+        }
+        for (from, to) in self.edges() {
+            writeln!(dot, "    \"{}\" -> \"{}\";", escape(from), escape(to)).unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// `node` for use inside a quoted DOT id.
+fn escape(node: impl Display) -> String {
+    node.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::Graph;
+
+    fn sample() -> Graph<i32> {
+        // 1 -> 2 -> 3 -> 4 and 2 -> 4, with 5 -> 6 -> 5 apart
+        Graph::from_edges([(1, 2), (2, 3), (3, 4), (2, 4), (5, 6), (6, 5)])
+    }
+
+    #[test]
+    fn building() {
+        let mut graph = sample();
+        assert_eq!(6, graph.len());
+        assert_eq!(6, graph.edge_count());
+        assert!(!graph.add_edge(1, 2));
+        assert!(graph.has_edge(&2, &4));
+        assert!(!graph.has_edge(&4, &2));
+        assert_eq!(vec![&3, &4], graph.successors(&2).collect::<Vec<_>>());
+        assert_eq!(vec![&3, &2], graph.predecessors(&4).collect::<Vec<_>>());
+        assert_eq!(0, graph.successors(&7).count());
+        assert_eq!(HashSet::from([2, 3, 4]), graph.reachable(&2));
+    }
+
+    #[test]
+    fn sorting_and_cycles() {
+        let graph = sample();
+        assert_eq!(
+            Ok(vec![1, 2, 3, 4]),
+            graph.subgraph(&[4, 3, 2, 1]).topo_sort()
+        );
+        assert_eq!(Err(vec![6, 5]), graph.topo_sort());
+        assert_eq!(None, graph.subgraph(&[1, 3, 5]).find_cycle());
+
+        let ring = Graph::from_edges([(0, 1), (1, 2), (2, 3), (3, 1)]);
+        let cycle = ring.find_cycle().unwrap();
+        assert_eq!(3, cycle.len());
+        for (index, node) in cycle.iter().enumerate() {
+            assert!(ring.has_edge(node, &cycle[(index + 1) % cycle.len()]));
+        }
+    }
+
+    #[test]
+    fn components() {
+        let components = sample().sccs();
+        assert_eq!(
+            vec![vec![4], vec![3], vec![2], vec![1], vec![5, 6]],
+            components
+        );
+
+        // deep enough to overflow the stack if the search recursed per node
+        let length = 200_000;
+        let chain = Graph::from_edges((0..length).map(|node| (node, node + 1)));
+        assert_eq!(length + 1, chain.sccs().len());
+        let mut ring = chain;
+        ring.add_edge(length, 0);
+        assert_eq!(vec![(0..=length).collect::<Vec<_>>()], ring.sccs());
+    }
+
+    #[test]
+    fn dot_export() {
+        let graph = Graph::from_edges([("a", "b")]);
+        assert_eq!(
+            "digraph \"rules\" {\n    \"a\";\n    \"b\";\n    \"a\" -> \"b\";\n}\n",
+            graph.to_dot("rules")
+        );
+
+        let quoted = Graph::from_edges([("say \"hi\"", "C:\\")]);
+        assert!(quoted
+            .to_dot("quotes")
+            .contains("    \"say \\\"hi\\\"\" -> \"C:\\\\\";\n"));
+        assert!(quoted
+            .to_dot("day-5 \"rules\"")
+            .starts_with("digraph \"day-5 \\\"rules\\\"\" {\n"));
+    }
+}
@@ -0,0 +1,490 @@
+//! Grids addressed by `(y, x)` coordinates, the same convention the days use.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::{Index, IndexMut, Range},
+};
+
+/// Up, right, down, left.
+pub const DIRECTIONS_4: [(i32, i32); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+/// Clockwise, starting from up.
+pub const DIRECTIONS_8: [(i32, i32); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+/// Inclusive bounding box of a set of coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Bounds {
+    pub min: (i32, i32),
+    pub max: (i32, i32),
+}
+
+impl Bounds {
+    pub fn height(&self) -> usize {
+        (self.max.0 - self.min.0 + 1) as usize
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max.1 - self.min.1 + 1) as usize
+    }
+
+    pub fn contains(&self, pos: (i32, i32)) -> bool {
+        (self.min.0..=self.max.0).contains(&pos.0) && (self.min.1..=self.max.1).contains(&pos.1)
+    }
+
+    fn extend(&mut self, pos: (i32, i32)) {
+        self.min = (self.min.0.min(pos.0), self.min.1.min(pos.1));
+        self.max = (self.max.0.max(pos.0), self.max.1.max(pos.1));
+    }
+}
+
+/// Dense rectangular grid stored row by row.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    height: usize,
+    width: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(height: usize, width: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            height,
+            width,
+            cells: vec![fill; height * width],
+        }
+    }
+
+    pub fn from_vec(height: usize, width: usize, cells: Vec<T>) -> Self {
+        assert_eq!(height * width, cells.len(), "cell count must match size");
+        Self {
+            height,
+            width,
+            cells,
+        }
+    }
+
+    /// Builds a grid from puzzle input, one line per row. Panics on ragged lines.
+    pub fn parse(input: &str, mut f: impl FnMut(char) -> T) -> Self {
+        let mut height = 0;
+        let mut width = None;
+        let mut cells = Vec::new();
+        for line in input.lines() {
+            let before = cells.len();
+            cells.extend(line.chars().map(&mut f));
+            let line_width = cells.len() - before;
+            assert_eq!(
+                *width.get_or_insert(line_width),
+                line_width,
+                "line {} has a different width",
+                height
+            );
+            height += 1;
+        }
+        Self {
+            height,
+            width: width.unwrap_or(0),
+            cells,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Bounding box of the grid, `None` when it has no cells.
+    pub fn bounds(&self) -> Option<Bounds> {
+        if self.cells.is_empty() {
+            return None;
+        }
+        Some(Bounds {
+            min: (0, 0),
+            max: (self.height as i32 - 1, self.width as i32 - 1),
+        })
+    }
+
+    pub fn in_bounds(&self, pos: (i32, i32)) -> bool {
+        pos.0 >= 0 && pos.1 >= 0 && (pos.0 as usize) < self.height && (pos.1 as usize) < self.width
+    }
+
+    fn index_of(&self, pos: (i32, i32)) -> Option<usize> {
+        self.in_bounds(pos)
+            .then(|| pos.0 as usize * self.width + pos.1 as usize)
+    }
+
+    pub fn get(&self, pos: (i32, i32)) -> Option<&T> {
+        self.index_of(pos).map(|index| &self.cells[index])
+    }
+
+    pub fn get_mut(&mut self, pos: (i32, i32)) -> Option<&mut T> {
+        self.index_of(pos).map(|index| &mut self.cells[index])
+    }
+
+    /// Stores `value` at `pos`, returning false if `pos` is outside the grid.
+    pub fn set(&mut self, pos: (i32, i32), value: T) -> bool {
+        match self.get_mut(pos) {
+            Some(cell) => {
+                *cell = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks(0) panics, an empty grid simply has no rows
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// Consecutive ranges of at most `rows_per_band` rows covering the grid,
+    /// for splitting work on it between threads.
+    pub fn row_bands(&self, rows_per_band: usize) -> Vec<Range<usize>> {
+        let rows_per_band = rows_per_band.max(1);
+        (0..self.height)
+            .step_by(rows_per_band)
+            .map(|start| start..(start + rows_per_band).min(self.height))
+            .collect()
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = (i32, i32)> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |index| ((index / width) as i32, (index % width) as i32))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((i32, i32), &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// In-bounds neighbours of `pos` along the given directions.
+    pub fn neighbours<'a>(
+        &'a self,
+        pos: (i32, i32),
+        directions: &'a [(i32, i32)],
+    ) -> impl Iterator<Item = (i32, i32)> + 'a {
+        directions
+            .iter()
+            .map(move |dir| (pos.0 + dir.0, pos.1 + dir.1))
+            .filter(|next| self.in_bounds(*next))
+    }
+
+    pub fn find(&self, mut predicate: impl FnMut(&T) -> bool) -> Option<(i32, i32)> {
+        self.iter()
+            .find(|(_, value)| predicate(value))
+            .map(|(pos, _)| pos)
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            height: self.height,
+            width: self.width,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T> Index<(i32, i32)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: (i32, i32)) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", pos))
+    }
+}
+
+impl<T> IndexMut<(i32, i32)> for Grid<T> {
+    fn index_mut(&mut self, pos: (i32, i32)) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", pos))
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Unbounded grid backed by a hash map. Only occupied cells are stored, so
+/// coordinates may be negative and the bounding box grows with the contents.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<(i32, i32), T>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a grid from puzzle input, storing only the chars `f` maps to `Some`.
+    pub fn parse(input: &str, mut f: impl FnMut(char) -> Option<T>) -> Self {
+        let cells = input
+            .lines()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .map(move |(x, char)| ((y as i32, x as i32), char))
+            })
+            .filter_map(|(pos, char)| f(char).map(|value| (pos, value)))
+            .collect();
+        Self { cells }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn height(&self) -> usize {
+        self.bounds().map_or(0, |bounds| bounds.height())
+    }
+
+    pub fn width(&self) -> usize {
+        self.bounds().map_or(0, |bounds| bounds.width())
+    }
+
+    /// Bounding box of the occupied cells, computed on demand.
+    pub fn bounds(&self) -> Option<Bounds> {
+        let mut positions = self.cells.keys();
+        let first = *positions.next()?;
+        let mut bounds = Bounds {
+            min: first,
+            max: first,
+        };
+        positions.for_each(|pos| bounds.extend(*pos));
+        Some(bounds)
+    }
+
+    /// Whether `pos` lies within the current bounding box.
+    pub fn in_bounds(&self, pos: (i32, i32)) -> bool {
+        self.bounds().is_some_and(|bounds| bounds.contains(pos))
+    }
+
+    pub fn get(&self, pos: (i32, i32)) -> Option<&T> {
+        self.cells.get(&pos)
+    }
+
+    pub fn get_mut(&mut self, pos: (i32, i32)) -> Option<&mut T> {
+        self.cells.get_mut(&pos)
+    }
+
+    /// Stores `value` at `pos`. Every coordinate is valid, so this always returns true.
+    pub fn set(&mut self, pos: (i32, i32), value: T) -> bool {
+        self.cells.insert(pos, value);
+        true
+    }
+
+    pub fn remove(&mut self, pos: (i32, i32)) -> Option<T> {
+        self.cells.remove(&pos)
+    }
+
+    /// Occupied positions, in no particular order.
+    pub fn positions(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.cells.keys().copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((i32, i32), &T)> {
+        self.cells.iter().map(|(pos, value)| (*pos, value))
+    }
+
+    /// Neighbours of `pos` along the given directions; the grid has no edges.
+    pub fn neighbours<'a>(
+        &'a self,
+        pos: (i32, i32),
+        directions: &'a [(i32, i32)],
+    ) -> impl Iterator<Item = (i32, i32)> + 'a {
+        directions
+            .iter()
+            .map(move |dir| (pos.0 + dir.0, pos.1 + dir.1))
+    }
+
+    pub fn find(&self, mut predicate: impl FnMut(&T) -> bool) -> Option<(i32, i32)> {
+        self.iter()
+            .find(|(_, value)| predicate(value))
+            .map(|(pos, _)| pos)
+    }
+
+    pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> SparseGrid<U> {
+        SparseGrid {
+            cells: self
+                .cells
+                .iter()
+                .map(|(pos, value)| (*pos, f(value)))
+                .collect(),
+        }
+    }
+
+    /// Copies the occupied cells into a dense grid covering the bounding box.
+    /// The dense grid's `(0, 0)` corresponds to the returned bounds' `min`.
+    pub fn to_grid(&self, fill: T) -> Option<(Grid<T>, Bounds)>
+    where
+        T: Clone,
+    {
+        let bounds = self.bounds()?;
+        let mut grid = Grid::new(bounds.height(), bounds.width(), fill);
+        for (pos, value) in self.iter() {
+            grid.set((pos.0 - bounds.min.0, pos.1 - bounds.min.1), value.clone());
+        }
+        Some((grid, bounds))
+    }
+
+    /// Renders the bounding box, using `empty` for unoccupied cells.
+    pub fn render(&self, empty: char) -> String
+    where
+        T: fmt::Display,
+    {
+        let Some(bounds) = self.bounds() else {
+            return String::new();
+        };
+        let mut output = String::new();
+        for y in bounds.min.0..=bounds.max.0 {
+            for x in bounds.min.1..=bounds.max.1 {
+                match self.get((y, x)) {
+                    Some(value) => output.push_str(&value.to_string()),
+                    None => output.push(empty),
+                }
+            }
+            output.push('\n');
+        }
+        output
+    }
+}
+
+impl<T> From<Grid<T>> for SparseGrid<T> {
+    fn from(grid: Grid<T>) -> Self {
+        let Grid { width, cells, .. } = grid;
+        let cells = cells
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| (((index / width) as i32, (index % width) as i32), value))
+            .collect();
+        Self { cells }
+    }
+}
+
+impl<T> Index<(i32, i32)> for SparseGrid<T> {
+    type Output = T;
+
+    fn index(&self, pos: (i32, i32)) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{:?} is not occupied", pos))
+    }
+}
+
+impl<T> IndexMut<(i32, i32)> for SparseGrid<T> {
+    fn index_mut(&mut self, pos: (i32, i32)) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{:?} is not occupied", pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bounds, Grid, SparseGrid, DIRECTIONS_4};
+
+    const INPUT: &str = "..#
+#..
+.#.";
+
+    #[test]
+    fn dense_parse_and_get() {
+        let grid = Grid::parse(INPUT, |char| char == '#');
+        assert_eq!(3, grid.height());
+        assert_eq!(3, grid.width());
+        assert_eq!(Some(&true), grid.get((0, 2)));
+        assert_eq!(Some(&false), grid.get((1, 1)));
+        assert_eq!(None, grid.get((-1, 0)));
+        assert_eq!(None, grid.get((0, 3)));
+        assert_eq!(3, grid.iter().filter(|(_, wall)| **wall).count());
+    }
+
+    #[test]
+    fn row_bands() {
+        let grid = Grid::new(5, 2, 0);
+        assert_eq!(vec![0..2, 2..4, 4..5], grid.row_bands(2));
+        assert_eq!(vec![0..5], grid.row_bands(10));
+        assert_eq!(5, grid.row_bands(0).len());
+        assert!(Grid::new(0, 0, 0).row_bands(3).is_empty());
+    }
+
+    #[test]
+    fn dense_set_out_of_bounds() {
+        let mut grid = Grid::new(2, 2, 0);
+        assert!(grid.set((1, 1), 5));
+        assert!(!grid.set((2, 0), 5));
+        assert_eq!(5, grid[(1, 1)]);
+        assert_eq!(
+            vec![(0, 1), (1, 0)],
+            grid.neighbours((0, 0), &DIRECTIONS_4).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn sparse_negative_coordinates() {
+        let mut grid = SparseGrid::new();
+        assert_eq!(None, grid.bounds());
+        grid.set((-3, 2), 'a');
+        grid.set((4, -1), 'b');
+        assert_eq!(
+            Some(Bounds {
+                min: (-3, -1),
+                max: (4, 2)
+            }),
+            grid.bounds()
+        );
+        assert_eq!(8, grid.height());
+        assert_eq!(4, grid.width());
+        assert_eq!('a', grid[(-3, 2)]);
+        assert_eq!(None, grid.get((0, 0)));
+    }
+
+    #[test]
+    fn sparse_matches_dense() {
+        let dense = Grid::parse(INPUT, |char| char);
+        let sparse = SparseGrid::parse(INPUT, |char| (char == '#').then_some(char));
+        assert_eq!(3, sparse.len());
+        assert_eq!(dense.to_string(), sparse.render('.'));
+
+        let (back, bounds) = SparseGrid::from(dense.clone()).to_grid('?').unwrap();
+        assert_eq!(dense, back);
+        assert_eq!((0, 0), bounds.min);
+    }
+}
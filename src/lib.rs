@@ -1,3 +1,17 @@
+pub mod bitgrid;
+pub mod components;
+pub mod cycle;
+pub mod graph;
+pub mod grid;
+pub mod math;
+pub mod parse;
+pub mod pathfinding;
+pub mod pattern;
+pub mod search;
+pub mod simulation;
+pub mod transform;
+pub mod word_search;
+
 use std::{
     env,
     io::{self, BufRead},
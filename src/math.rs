@@ -0,0 +1,153 @@
+//! Number theory and decimal digit helpers.
+
+/// Greatest common divisor, always non-negative. `gcd(0, 0)` is 0.
+pub fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a as i64
+}
+
+/// Least common multiple, always non-negative. Panics on overflow.
+pub fn lcm(a: i64, b: i64) -> i64 {
+    checked_lcm(a, b).expect("lcm overflowed")
+}
+
+pub fn checked_lcm(a: i64, b: i64) -> Option<i64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b).map(i64::abs)
+}
+
+/// Extended Euclid: returns `(g, x, y)` with `a * x + b * y == g == gcd(a, b)`.
+pub fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_x, x) = (x, old_x - quotient * x);
+        (old_y, y) = (y, old_y - quotient * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// `x` in `0..modulus` with `a * x ≡ 1 (mod modulus)`, if `a` and `modulus` are coprime.
+pub fn mod_inverse(a: i64, modulus: i64) -> Option<i64> {
+    let (g, x, _) = extended_gcd(a.rem_euclid(modulus), modulus);
+    (g == 1).then(|| x.rem_euclid(modulus))
+}
+
+/// Chinese remainder theorem over `(residue, modulus)` pairs. Returns the
+/// combined `(residue, modulus)`, or `None` if the congruences contradict each
+/// other or the combined modulus overflows. Moduli need not be coprime.
+pub fn crt(congruences: &[(i64, i64)]) -> Option<(i64, i64)> {
+    congruences
+        .iter()
+        .try_fold((0, 1), |(residue, modulus), &(r, m)| {
+            let r = r.rem_euclid(m);
+            let (g, p, _) = extended_gcd(modulus, m);
+            if (r - residue) % g != 0 {
+                return None;
+            }
+            let combined = checked_lcm(modulus, m)?;
+            // residue + modulus * k ≡ r (mod m), solved for k
+            let k = (r - residue) as i128 / g as i128 * p as i128 % (m / g) as i128;
+            let value = (residue as i128 + modulus as i128 * k).rem_euclid(combined as i128);
+            Some((value as i64, combined))
+        })
+}
+
+/// Number of decimal digits, counting 0 as one digit.
+pub fn count_digits(n: u64) -> u32 {
+    n.checked_ilog10().map_or(1, |log| log + 1)
+}
+
+/// Splits off the last `right_digits` digits: `split_digits(1234, 1) == (123, 4)`.
+pub fn split_digits(n: u64, right_digits: u32) -> (u64, u64) {
+    match 10_u64.checked_pow(right_digits) {
+        Some(divisor) => (n / divisor, n % divisor),
+        None => (0, n),
+    }
+}
+
+/// Splits an even-length number into its two halves, `1234` into `(12, 34)`.
+pub fn split_half(n: u64) -> Option<(u64, u64)> {
+    let digits = count_digits(n);
+    digits
+        .is_multiple_of(2)
+        .then(|| split_digits(n, digits / 2))
+}
+
+/// Writes the digits of `b` after those of `a`: `concat(12, 345) == 12345`.
+/// Panics on overflow.
+pub fn concat(a: u64, b: u64) -> u64 {
+    checked_concat(a, b).expect("concat overflowed")
+}
+
+pub fn checked_concat(a: u64, b: u64) -> Option<u64> {
+    a.checked_mul(10_u64.checked_pow(count_digits(b))?)?
+        .checked_add(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        checked_concat, concat, count_digits, crt, extended_gcd, gcd, lcm, mod_inverse,
+        split_digits, split_half,
+    };
+
+    #[test]
+    fn divisors() {
+        assert_eq!(6, gcd(12, 18));
+        assert_eq!(6, gcd(-12, 18));
+        assert_eq!(5, gcd(0, 5));
+        assert_eq!(36, lcm(12, 18));
+        assert_eq!(10403, lcm(101, 103));
+        assert_eq!(0, lcm(0, 3));
+
+        for (a, b) in [(240, 46), (-7, 3), (3, -7), (0, 4)] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(gcd(a, b), g);
+            assert_eq!(g, a * x + b * y);
+        }
+    }
+
+    #[test]
+    fn inverses_and_crt() {
+        assert_eq!(Some(4), mod_inverse(3, 11));
+        assert_eq!(Some(8), mod_inverse(-4, 11));
+        assert_eq!(None, mod_inverse(4, 8));
+
+        assert_eq!(Some((23, 105)), crt(&[(2, 3), (3, 5), (2, 7)]));
+        // non-coprime but consistent
+        assert_eq!(Some((10, 12)), crt(&[(4, 6), (2, 4)]));
+        assert_eq!(None, crt(&[(1, 4), (2, 6)]));
+        assert_eq!(Some((0, 1)), crt(&[]));
+
+        let (t, period) = crt(&[(12, 101), (55, 103)]).unwrap();
+        assert_eq!(10403, period);
+        assert_eq!((12, 55), (t % 101, t % 103));
+    }
+
+    #[test]
+    fn digits() {
+        assert_eq!(1, count_digits(0));
+        assert_eq!(4, count_digits(1000));
+        assert_eq!(20, count_digits(u64::MAX));
+        assert_eq!((123, 4), split_digits(1234, 1));
+        assert_eq!((0, 12), split_digits(12, 30));
+        assert_eq!(Some((10, 0)), split_half(1000));
+        assert_eq!(None, split_half(100));
+        assert_eq!(12345, concat(12, 345));
+        assert_eq!(10, concat(1, 0));
+        assert_eq!(None, checked_concat(u64::MAX / 10, 99));
+    }
+}
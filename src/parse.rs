@@ -0,0 +1,220 @@
+//! Helpers for reading puzzle input.
+
+use std::{fmt, str::FromStr};
+
+use nom::{
+    bytes::complete::take_till1,
+    character::complete::{char, digit1, one_of},
+    combinator::{map_res, opt, recognize},
+    multi::separated_list1,
+    sequence::{pair, separated_pair},
+    Finish, IResult,
+};
+
+/// Where and why parsing failed. Lines and columns are 1-based.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    /// The offending line of input.
+    pub context: String,
+}
+
+impl ParseError {
+    /// Error at the position in `input` where `rest` starts.
+    pub fn at(input: &str, rest: &str, message: impl Into<String>) -> Self {
+        let offset = input.len() - rest.len();
+        let line_start = input[..offset].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |index| offset + index);
+        Self {
+            line: input[..offset].matches('\n').count() + 1,
+            column: input[line_start..offset].chars().count() + 1,
+            message: message.into(),
+            context: input[line_start..line_end].to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )?;
+        writeln!(f, "{}", self.context)?;
+        write!(f, "{}^", " ".repeat(self.column - 1))
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Runs a nom parser over the whole of `input`. Leftover input is an error.
+pub fn run_parser<'a, O>(
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+    input: &'a str,
+) -> Result<O, ParseError> {
+    match parser(input).finish() {
+        Ok(("", output)) => Ok(output),
+        Ok((rest, _)) => Err(ParseError::at(input, rest, "unexpected trailing input")),
+        Err(error) => Err(ParseError::at(
+            input,
+            error.input,
+            format!("failed to parse ({:?})", error.code),
+        )),
+    }
+}
+
+/// Optionally signed integer, e.g. `-12` or `+3`.
+pub fn signed<T: FromStr>(input: &str) -> IResult<&str, T> {
+    map_res(recognize(pair(opt(one_of("+-")), digit1)), str::parse)(input)
+}
+
+pub fn unsigned<T: FromStr>(input: &str) -> IResult<&str, T> {
+    map_res(digit1, str::parse)(input)
+}
+
+/// `key=a,b,...` with at least one value, e.g. `p=0,4`.
+pub fn key_values<T: FromStr>(input: &str) -> IResult<&str, (&str, Vec<T>)> {
+    separated_pair(
+        take_till1(|char| char == '='),
+        char('='),
+        separated_list1(char(','), signed),
+    )(input)
+}
+
+/// Parses a whole `key=a,b` field.
+pub fn key_pair<T: FromStr>(input: &str) -> Result<(&str, T, T), ParseError> {
+    let pair = |input| {
+        separated_pair(
+            take_till1(|char| char == '='),
+            char('='),
+            separated_pair(signed, char(','), signed),
+        )(input)
+    };
+    run_parser(pair, input).map(|(key, (a, b))| (key, a, b))
+}
+
+/// Splits input into the blocks separated by blank lines. Blocks keep their
+/// inner newlines but lose the trailing one.
+pub fn sections(input: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let mut start = None;
+    let mut end = 0;
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            if let Some(start) = start.take() {
+                sections.push(input[start..end].trim_end_matches(['\r', '\n']));
+            }
+        } else {
+            start.get_or_insert(offset);
+            end = offset + line.len();
+        }
+        offset += line.len();
+    }
+    if let Some(start) = start {
+        sections.push(input[start..end].trim_end_matches(['\r', '\n']));
+    }
+    sections
+}
+
+/// Every integer in `line`, where a `-` or `+` directly before the digits is its sign.
+pub fn signed_integers<T>(line: &str) -> Vec<T>
+where
+    T: FromStr,
+    T::Err: fmt::Debug,
+{
+    integers(line, true)
+}
+
+/// Every run of digits in `line`; signs are ignored.
+pub fn unsigned_integers<T>(line: &str) -> Vec<T>
+where
+    T: FromStr,
+    T::Err: fmt::Debug,
+{
+    integers(line, false)
+}
+
+fn integers<T>(line: &str, allow_sign: bool) -> Vec<T>
+where
+    T: FromStr,
+    T::Err: fmt::Debug,
+{
+    let bytes = line.as_bytes();
+    let is_digit = |index: usize| bytes.get(index).is_some_and(u8::is_ascii_digit);
+    let mut numbers = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        let signed = allow_sign && matches!(bytes[index], b'-' | b'+') && is_digit(index + 1);
+        if !signed && !is_digit(index) {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        index += 1;
+        while is_digit(index) {
+            index += 1;
+        }
+        let number = &line[start..index];
+        numbers.push(
+            number
+                .parse()
+                .unwrap_or_else(|err| panic!("{} does not fit: {:?}", number, err)),
+        );
+    }
+    numbers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        key_pair, key_values, run_parser, sections, signed, signed_integers, unsigned_integers,
+    };
+
+    #[test]
+    fn blank_line_sections() {
+        let input = "47|53\n97|13\n\n75,47\n   \n\n#..#\r\n\r\n<>^v\n";
+        assert_eq!(
+            vec!["47|53\n97|13", "75,47", "#..#", "<>^v"],
+            sections(input)
+        );
+        assert!(sections("\n\n").is_empty());
+    }
+
+    #[test]
+    fn integer_extraction() {
+        assert_eq!(vec![0, 4, 3, -3], signed_integers::<i32>("p=0,4 v=3,-3"));
+        assert_eq!(vec![0, 4, 3, 3], unsigned_integers::<u32>("p=0,4 v=3,-3"));
+        assert_eq!(vec![190, 10, 19], unsigned_integers::<u64>("190: 10 19"));
+        assert_eq!(vec![5, -2], signed_integers::<i64>("x5 - -2-"));
+        assert!(signed_integers::<i32>("no numbers").is_empty());
+    }
+
+    #[test]
+    fn key_value_fields() {
+        assert_eq!(Ok(("p", 0, 4)), key_pair::<i32>("p=0,4"));
+        assert_eq!(Ok(("v", 3, -3)), key_pair::<i32>("v=3,-3"));
+        assert_eq!(
+            Ok(("", ("pos", vec![1, -2, 3]))),
+            key_values::<i32>("pos=1,-2,3")
+        );
+    }
+
+    #[test]
+    fn error_positions() {
+        let error = key_pair::<i32>("p=0,x").unwrap_err();
+        assert_eq!((1, 5), (error.line, error.column));
+
+        let error = run_parser(signed::<i32>, "12\n").unwrap_err();
+        assert_eq!((1, 3), (error.line, error.column));
+        assert_eq!(
+            "line 1, column 3: unexpected trailing input\n12\n  ^",
+            error.to_string()
+        );
+    }
+}
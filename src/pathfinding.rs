@@ -0,0 +1,227 @@
+//! Shortest paths over rectangular grids with 4-neighbour moves.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+};
+
+use crate::grid::{Grid, DIRECTIONS_4};
+
+/// Distances from the nearest start, with the predecessors needed to rebuild paths.
+#[derive(Clone, Debug)]
+pub struct DistanceMap {
+    distances: Grid<Option<u64>>,
+    previous: Grid<Option<(i32, i32)>>,
+}
+
+impl DistanceMap {
+    fn new(height: usize, width: usize) -> Self {
+        Self {
+            distances: Grid::new(height, width, None),
+            previous: Grid::new(height, width, None),
+        }
+    }
+
+    /// Distance to `pos`, `None` if it is unreachable or outside the grid.
+    pub fn distance(&self, pos: (i32, i32)) -> Option<u64> {
+        self.distances.get(pos).copied().flatten()
+    }
+
+    pub fn distances(&self) -> &Grid<Option<u64>> {
+        &self.distances
+    }
+
+    /// Reachable positions and their distances, in row-major order.
+    pub fn reachable(&self) -> impl Iterator<Item = ((i32, i32), u64)> + '_ {
+        self.distances
+            .iter()
+            .filter_map(|(pos, distance)| distance.map(|distance| (pos, distance)))
+    }
+
+    /// Path from the start it was reached from to `target`, both inclusive.
+    pub fn path_to(&self, target: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        self.distance(target)?;
+        let mut path = vec![target];
+        let mut current = target;
+        while let Some(previous) = self.previous[current] {
+            path.push(previous);
+            current = previous;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+pub fn manhattan(a: (i32, i32), b: (i32, i32)) -> u64 {
+    (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as u64
+}
+
+/// Breadth-first search from every start at once. `passable(from, to)` decides
+/// whether a single step between two neighbouring cells is allowed.
+pub fn bfs<T>(
+    grid: &Grid<T>,
+    starts: impl IntoIterator<Item = (i32, i32)>,
+    mut passable: impl FnMut((i32, i32), (i32, i32)) -> bool,
+) -> DistanceMap {
+    let mut map = DistanceMap::new(grid.height(), grid.width());
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if map.distance(start).is_none() && map.distances.set(start, Some(0)) {
+            queue.push_back(start);
+        }
+    }
+
+    while let Some(pos) = queue.pop_front() {
+        let distance = map.distances[pos].unwrap();
+        for next in grid.neighbours(pos, &DIRECTIONS_4) {
+            if map.distances[next].is_none() && passable(pos, next) {
+                map.distances[next] = Some(distance + 1);
+                map.previous[next] = Some(pos);
+                queue.push_back(next);
+            }
+        }
+    }
+    map
+}
+
+/// Dijkstra from every start at once. `cost(from, to)` returns the price of a
+/// step between neighbouring cells, or `None` if the step is not allowed.
+pub fn dijkstra<T>(
+    grid: &Grid<T>,
+    starts: impl IntoIterator<Item = (i32, i32)>,
+    cost: impl FnMut((i32, i32), (i32, i32)) -> Option<u64>,
+) -> DistanceMap {
+    search(grid, starts, None, cost, |_| 0).0
+}
+
+/// A* from the starts to `goal`, returning the total cost and the path.
+/// `heuristic` must never overestimate the remaining cost, e.g. [`manhattan`]
+/// when every step costs at least one.
+pub fn astar<T>(
+    grid: &Grid<T>,
+    starts: impl IntoIterator<Item = (i32, i32)>,
+    goal: (i32, i32),
+    cost: impl FnMut((i32, i32), (i32, i32)) -> Option<u64>,
+    heuristic: impl FnMut((i32, i32)) -> u64,
+) -> Option<(u64, Vec<(i32, i32)>)> {
+    let (map, found) = search(grid, starts, Some(goal), cost, heuristic);
+    if !found {
+        return None;
+    }
+    Some((map.distance(goal)?, map.path_to(goal)?))
+}
+
+fn search<T>(
+    grid: &Grid<T>,
+    starts: impl IntoIterator<Item = (i32, i32)>,
+    goal: Option<(i32, i32)>,
+    mut cost: impl FnMut((i32, i32), (i32, i32)) -> Option<u64>,
+    mut heuristic: impl FnMut((i32, i32)) -> u64,
+) -> (DistanceMap, bool) {
+    let mut map = DistanceMap::new(grid.height(), grid.width());
+    let mut done = Grid::new(grid.height(), grid.width(), false);
+    let mut queue = BinaryHeap::new();
+    for start in starts {
+        if map.distances.set(start, Some(0)) {
+            queue.push(Reverse((heuristic(start), 0, start)));
+        }
+    }
+
+    while let Some(Reverse((_, distance, pos))) = queue.pop() {
+        if done[pos] {
+            continue;
+        }
+        done[pos] = true;
+        if Some(pos) == goal {
+            return (map, true);
+        }
+
+        for next in grid.neighbours(pos, &DIRECTIONS_4) {
+            if done[next] {
+                continue;
+            }
+            let Some(step) = cost(pos, next) else {
+                continue;
+            };
+            let next_distance = distance + step;
+            if map.distances[next].is_none_or(|known| next_distance < known) {
+                map.distances[next] = Some(next_distance);
+                map.previous[next] = Some(pos);
+                queue.push(Reverse((
+                    next_distance + heuristic(next),
+                    next_distance,
+                    next,
+                )));
+            }
+        }
+    }
+    (map, goal.is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{astar, bfs, dijkstra, manhattan};
+    use crate::grid::Grid;
+
+    const MAZE: &str = "S..#....
+.#.#.##.
+.#...#..
+.####.#.
+......#E";
+
+    fn walls() -> Grid<bool> {
+        Grid::parse(MAZE, |char| char == '#')
+    }
+
+    #[test]
+    fn bfs_distances_and_path() {
+        let walls = walls();
+        let map = bfs(&walls, [(0, 0)], |_, to| !walls[to]);
+        assert_eq!(Some(15), map.distance((4, 7)));
+        assert_eq!(None, map.distance((0, 3)));
+
+        let path = map.path_to((4, 7)).unwrap();
+        assert_eq!(16, path.len());
+        assert_eq!((0, 0), path[0]);
+        assert!(path.iter().all(|pos| !walls[*pos]));
+        assert!(path.windows(2).all(|step| manhattan(step[0], step[1]) == 1));
+    }
+
+    #[test]
+    fn multi_source() {
+        let walls = walls();
+        let map = bfs(&walls, [(0, 0), (4, 7)], |_, to| !walls[to]);
+        assert_eq!(Some(0), map.distance((4, 7)));
+        assert_eq!(Some(7), map.distance((0, 4)));
+        assert_eq!(vec![(4, 7), (3, 7)], map.path_to((3, 7)).unwrap());
+    }
+
+    #[test]
+    fn weighted_search() {
+        // stepping onto a digit costs that digit
+        let grid = Grid::parse("1911\n1919\n1119", |char| char.to_digit(10).unwrap() as u64);
+        let cost = |_, to| Some(grid[to]);
+
+        let map = dijkstra(&grid, [(0, 0)], cost);
+        assert_eq!(Some(13), map.distance((2, 3)));
+        assert_eq!(Some(7), map.distance((0, 3)));
+
+        let (total, path) = astar(&grid, [(0, 0)], (0, 3), cost, |pos| manhattan(pos, (0, 3)))
+            .expect("goal is reachable");
+        assert_eq!(7, total);
+        assert_eq!(8, path.len());
+    }
+
+    #[test]
+    fn astar_unreachable() {
+        let walls = walls();
+        let res = astar(
+            &walls,
+            [(0, 0)],
+            (0, 3),
+            |_, to| (!walls[to]).then_some(1),
+            |pos| manhattan(pos, (0, 3)),
+        );
+        assert_eq!(None, res);
+    }
+}
@@ -0,0 +1,230 @@
+//! Matching small 2D templates with wildcards against a grid.
+
+use std::ops::Range;
+
+use rayon::prelude::*;
+
+use crate::{grid::Grid, transform::Transform};
+
+/// Rectangular template where `None` cells match anything.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Pattern<T> {
+    cells: Grid<Option<T>>,
+}
+
+/// Where a pattern matched: the top-left corner of the placed variant and the
+/// transform that produced it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Placement {
+    pub pos: (i32, i32),
+    pub transform: Transform,
+}
+
+impl Pattern<char> {
+    /// Parses rows separated by newlines or `/`, e.g. `M.S / .A. / M.S`.
+    /// Spaces are ignored and `.` is a wildcard.
+    pub fn parse(input: &str) -> Self {
+        let rows: Vec<String> = input
+            .split(['\n', '/'])
+            .map(|row| row.chars().filter(|char| *char != ' ').collect())
+            .filter(|row: &String| !row.is_empty())
+            .collect();
+        let cells = Grid::parse(&rows.join("\n"), |char| (char != '.').then_some(char));
+        Self { cells }
+    }
+}
+
+impl<T: PartialEq + Clone> Pattern<T> {
+    pub fn new(cells: Grid<Option<T>>) -> Self {
+        Self { cells }
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.height()
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells.width()
+    }
+
+    /// The same pattern over other cell values, e.g. bytes instead of chars.
+    pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> Pattern<U> {
+        Pattern {
+            cells: self.cells.map(|cell| cell.as_ref().map(&mut f)),
+        }
+    }
+
+    pub fn transformed(&self, transform: Transform) -> Self {
+        Self {
+            cells: self.cells.view(transform).to_grid(),
+        }
+    }
+
+    /// The distinct variants of this pattern under `transforms`, each paired
+    /// with the first transform that produced it.
+    pub fn variants(&self, transforms: &[Transform]) -> Vec<(Transform, Self)> {
+        let mut variants: Vec<(Transform, Self)> = Vec::new();
+        for transform in transforms {
+            let variant = self.transformed(*transform);
+            if variants.iter().all(|(_, known)| *known != variant) {
+                variants.push((*transform, variant));
+            }
+        }
+        variants
+    }
+
+    /// Grid cells under the pattern's non-wildcard cells when its top-left
+    /// corner is at `pos`.
+    pub fn covered(&self, pos: (i32, i32)) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.cells
+            .iter()
+            .filter(|(_, cell)| cell.is_some())
+            .map(move |(offset, _)| (pos.0 + offset.0, pos.1 + offset.1))
+    }
+
+    /// Whether the pattern matches with its top-left corner at `pos`.
+    pub fn matches_at(&self, grid: &Grid<T>, pos: (i32, i32)) -> bool {
+        self.cells.iter().all(|(offset, expected)| {
+            let actual = grid.get((pos.0 + offset.0, pos.1 + offset.1));
+            match (expected, actual) {
+                (_, None) => false,
+                (None, Some(_)) => true,
+                (Some(expected), Some(actual)) => expected == actual,
+            }
+        })
+    }
+
+    /// Top-left corners of every placement of the pattern as given.
+    pub fn find(&self, grid: &Grid<T>) -> Vec<(i32, i32)> {
+        let rows = (grid.height() + 1).saturating_sub(self.height()) as i32;
+        let columns = (grid.width() + 1).saturating_sub(self.width()) as i32;
+        (0..rows)
+            .flat_map(|y| (0..columns).map(move |x| (y, x)))
+            .filter(|pos| self.matches_at(grid, *pos))
+            .collect()
+    }
+
+    /// Every placement of every distinct variant under `transforms`, ordered
+    /// by position and then by variant. Pass `&Transform::ROTATIONS` or
+    /// `&Transform::ALL` to allow turning the shape.
+    pub fn find_all(&self, grid: &Grid<T>, transforms: &[Transform]) -> Vec<Placement> {
+        self.find_all_in_rows(grid, transforms, 0..grid.height())
+    }
+
+    /// Same as [`Self::find_all`], searching bands of `rows_per_band` rows in
+    /// parallel.
+    pub fn find_all_in_bands(
+        &self,
+        grid: &Grid<T>,
+        transforms: &[Transform],
+        rows_per_band: usize,
+    ) -> Vec<Placement>
+    where
+        T: Sync,
+    {
+        let variants = self.variants(transforms);
+        grid.row_bands(rows_per_band)
+            .into_par_iter()
+            .flat_map_iter(|rows| Self::variants_in_rows(&variants, grid, rows))
+            .collect()
+    }
+
+    /// Placements of every distinct variant with the top-left corner in
+    /// `rows`, ordered by position and then by variant. Splitting the grid
+    /// into consecutive row ranges and joining the results gives the same list
+    /// as searching all rows at once.
+    pub fn find_all_in_rows(
+        &self,
+        grid: &Grid<T>,
+        transforms: &[Transform],
+        rows: Range<usize>,
+    ) -> Vec<Placement> {
+        Self::variants_in_rows(&self.variants(transforms), grid, rows)
+    }
+
+    fn variants_in_rows(
+        variants: &[(Transform, Self)],
+        grid: &Grid<T>,
+        rows: Range<usize>,
+    ) -> Vec<Placement> {
+        let rows = rows.start.min(grid.height())..rows.end.min(grid.height());
+        let columns = grid.width() as i32;
+        rows.flat_map(|y| (0..columns).map(move |x| (y as i32, x)))
+            .flat_map(|pos| {
+                variants
+                    .iter()
+                    .filter(move |(_, variant)| variant.matches_at(grid, pos))
+                    .map(move |(transform, _)| Placement {
+                        pos,
+                        transform: *transform,
+                    })
+            })
+            .collect()
+    }
+
+    pub fn count(&self, grid: &Grid<T>, transforms: &[Transform]) -> usize {
+        self.find_all(grid, transforms).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pattern;
+    use crate::{grid::Grid, transform::Transform};
+
+    #[test]
+    fn parse_wildcards() {
+        let pattern = Pattern::parse("M.S / .A. / M.S");
+        assert_eq!((3, 3), (pattern.height(), pattern.width()));
+        assert_eq!(pattern, Pattern::parse("M.S\n.A.\nM.S"));
+        assert_eq!(
+            vec![(1, 2), (1, 4), (2, 3), (3, 2), (3, 4)],
+            pattern.covered((1, 2)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn variants_are_distinct() {
+        let x_mas = Pattern::parse("M.S/.A./M.S");
+        assert_eq!(4, x_mas.variants(&Transform::ROTATIONS).len());
+        // every mirror image of the X is also a rotation of it
+        assert_eq!(4, x_mas.variants(&Transform::ALL).len());
+
+        let l_shape = Pattern::parse("#./#./##");
+        assert_eq!(8, l_shape.variants(&Transform::ALL).len());
+    }
+
+    #[test]
+    fn placements() {
+        let grid = Grid::parse("M.M.\n.A..\nS.S.\n.A..\nM.M.", |char| char);
+        let x_mas = Pattern::parse("M.S/.A./M.S");
+        let placements = x_mas.find_all(&grid, &Transform::ROTATIONS);
+        assert_eq!(2, placements.len());
+        assert!(placements.iter().any(|placement| placement.pos == (0, 0)));
+        assert!(placements.iter().any(|placement| placement.pos == (2, 0)));
+        assert_eq!(0, x_mas.count(&grid, &[Transform::Identity]));
+    }
+
+    #[test]
+    fn placements_in_rows() {
+        let grid = Grid::parse("M.M.\n.A..\nS.S.\n.A..\nM.M.", |char| char);
+        let x_mas = Pattern::parse("M.S/.A./M.S");
+        let all = x_mas.find_all_in_rows(&grid, &Transform::ROTATIONS, 0..5);
+        assert_eq!(2, all.len());
+        let mut joined = x_mas.find_all_in_rows(&grid, &Transform::ROTATIONS, 0..1);
+        joined.extend(x_mas.find_all_in_rows(&grid, &Transform::ROTATIONS, 1..3));
+        joined.extend(x_mas.find_all_in_rows(&grid, &Transform::ROTATIONS, 3..10));
+        assert_eq!(all, joined);
+        for rows_per_band in 1..=5 {
+            let banded = x_mas.find_all_in_bands(&grid, &Transform::ROTATIONS, rows_per_band);
+            assert_eq!(all, banded);
+        }
+        // bytes place the pattern where chars do
+        let bytes = grid.map(|char| *char as u8);
+        let x_mas = x_mas.map(|char| *char as u8);
+        assert_eq!(
+            all,
+            x_mas.find_all_in_bands(&bytes, &Transform::ROTATIONS, 2)
+        );
+    }
+}
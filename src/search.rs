@@ -0,0 +1,385 @@
+//! Searches over arbitrary hashable states described by a successor function.
+//!
+//! Every search keeps a visited set, so a state is expanded at most once and
+//! cyclic state spaces terminate.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+/// Interned states with the parent links needed to rebuild a path.
+struct Explored<S> {
+    states: Vec<S>,
+    parents: Vec<Option<usize>>,
+    ids: HashMap<S, usize>,
+}
+
+impl<S: Hash + Eq + Clone> Explored<S> {
+    fn new() -> Self {
+        Self {
+            states: Vec::new(),
+            parents: Vec::new(),
+            ids: HashMap::new(),
+        }
+    }
+
+    /// Records `state`, returning its id if it had not been seen before.
+    fn insert(&mut self, state: S, parent: Option<usize>) -> Option<usize> {
+        if self.ids.contains_key(&state) {
+            return None;
+        }
+        let id = self.states.len();
+        self.ids.insert(state.clone(), id);
+        self.states.push(state);
+        self.parents.push(parent);
+        Some(id)
+    }
+
+    fn path(&self, mut id: usize) -> Vec<S> {
+        let mut path = vec![self.states[id].clone()];
+        while let Some(parent) = self.parents[id] {
+            path.push(self.states[parent].clone());
+            id = parent;
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Depth-first search from `start`, returning the path to the first goal found.
+pub fn dfs<S, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<Vec<S>>
+where
+    S: Hash + Eq + Clone,
+    I: IntoIterator<Item = S>,
+{
+    let mut explored = Explored::new();
+    let mut stack: Vec<usize> = explored.insert(start, None).into_iter().collect();
+
+    while let Some(id) = stack.pop() {
+        let state = explored.states[id].clone();
+        if is_goal(&state) {
+            return Some(explored.path(id));
+        }
+        // pushed in reverse so the first successor is expanded first
+        let next: Vec<_> = successors(&state)
+            .into_iter()
+            .filter_map(|next| explored.insert(next, Some(id)))
+            .collect();
+        stack.extend(next.into_iter().rev());
+    }
+    None
+}
+
+/// Breadth-first search from all `starts`, returning a shortest path to a goal.
+pub fn bfs<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<Vec<S>>
+where
+    S: Hash + Eq + Clone,
+    I: IntoIterator<Item = S>,
+{
+    let mut explored = Explored::new();
+    let mut queue: VecDeque<usize> = starts
+        .into_iter()
+        .filter_map(|start| explored.insert(start, None))
+        .collect();
+
+    while let Some(id) = queue.pop_front() {
+        let state = explored.states[id].clone();
+        if is_goal(&state) {
+            return Some(explored.path(id));
+        }
+        for next in successors(&state) {
+            queue.extend(explored.insert(next, Some(id)));
+        }
+    }
+    None
+}
+
+/// Every state reachable from `starts`, including the starts themselves.
+pub fn reachable<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+) -> HashSet<S>
+where
+    S: Hash + Eq + Clone,
+    I: IntoIterator<Item = S>,
+{
+    let mut seen = HashSet::new();
+    let mut stack: Vec<S> = starts
+        .into_iter()
+        .filter(|start| seen.insert(start.clone()))
+        .collect();
+    while let Some(state) = stack.pop() {
+        for next in successors(&state) {
+            if seen.insert(next.clone()) {
+                stack.push(next);
+            }
+        }
+    }
+    seen
+}
+
+/// Dijkstra over states whose successors come with a step cost. Returns the
+/// cheapest total cost and path to a goal.
+pub fn dijkstra<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<(u64, Vec<S>)>
+where
+    S: Hash + Eq + Clone,
+    I: IntoIterator<Item = (S, u64)>,
+{
+    best_first(starts, successors, is_goal, |_, cost| cost)
+}
+
+/// Expands states in order of `priority(state, cost_so_far)`, lowest first.
+/// `cost` alone gives Dijkstra, `cost + heuristic` gives A*, and the heuristic
+/// alone gives a greedy search. Returns the cost and path of the goal reached.
+pub fn best_first<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+    mut priority: impl FnMut(&S, u64) -> u64,
+) -> Option<(u64, Vec<S>)>
+where
+    S: Hash + Eq + Clone,
+    I: IntoIterator<Item = (S, u64)>,
+{
+    let mut explored = Explored::new();
+    let mut costs: Vec<u64> = Vec::new();
+    let mut done: Vec<bool> = Vec::new();
+    let mut queue = BinaryHeap::new();
+
+    for start in starts {
+        if let Some(id) = explored.insert(start, None) {
+            costs.push(0);
+            done.push(false);
+            queue.push(Reverse((priority(&explored.states[id], 0), id)));
+        }
+    }
+
+    while let Some(Reverse((_, id))) = queue.pop() {
+        if done[id] {
+            continue;
+        }
+        done[id] = true;
+        let state = explored.states[id].clone();
+        if is_goal(&state) {
+            return Some((costs[id], explored.path(id)));
+        }
+
+        for (next, step) in successors(&state) {
+            let cost = costs[id] + step;
+            let next_id = match explored.ids.get(&next) {
+                Some(next_id) if done[*next_id] || costs[*next_id] <= cost => continue,
+                Some(next_id) => {
+                    costs[*next_id] = cost;
+                    explored.parents[*next_id] = Some(id);
+                    *next_id
+                }
+                None => {
+                    costs.push(cost);
+                    done.push(false);
+                    explored.insert(next, Some(id)).unwrap()
+                }
+            };
+            queue.push(Reverse((
+                priority(&explored.states[next_id], cost),
+                next_id,
+            )));
+        }
+    }
+    None
+}
+
+/// Looks for a cycle reachable from `start`, returning the states on it in
+/// order. A deterministic walk, where each state has at most one successor,
+/// is cheaper to check with [`walk_loops`].
+pub fn find_cycle<S, I>(start: S, mut successors: impl FnMut(&S) -> I) -> Option<Vec<S>>
+where
+    S: Hash + Eq + Clone,
+    I: IntoIterator<Item = S>,
+{
+    let mut on_path: HashMap<S, usize> = HashMap::new();
+    let mut finished: HashSet<S> = HashSet::new();
+    let mut pending = vec![successors(&start).into_iter()];
+    on_path.insert(start.clone(), 0);
+    let mut path = vec![start];
+
+    while let Some(iter) = pending.last_mut() {
+        match iter.next() {
+            Some(next) => {
+                if let Some(index) = on_path.get(&next) {
+                    return Some(path.split_off(*index));
+                }
+                if finished.contains(&next) {
+                    continue;
+                }
+                on_path.insert(next.clone(), path.len());
+                pending.push(successors(&next).into_iter());
+                path.push(next);
+            }
+            None => {
+                pending.pop();
+                let state = path.pop().unwrap();
+                on_path.remove(&state);
+                finished.insert(state);
+            }
+        }
+    }
+    None
+}
+
+/// Follows a deterministic walk from `start`, returning true once a state
+/// repeats and false once `step` has no successor. `visit` records a state
+/// and returns false if it was already recorded, so a state type with a
+/// cheaper set than hashing, such as a bitset, can bring its own.
+pub fn walk_loops<S>(
+    start: S,
+    mut step: impl FnMut(&S) -> Option<S>,
+    mut visit: impl FnMut(&S) -> bool,
+) -> bool {
+    let mut state = start;
+    loop {
+        if !visit(&state) {
+            return true;
+        }
+        match step(&state) {
+            Some(next) => state = next,
+            None => return false,
+        }
+    }
+}
+
+/// Counts the distinct paths from `start` to goal states, memoizing the count
+/// for every state. Goal states end a path. The state space must be acyclic.
+pub fn count_paths<S, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> u64
+where
+    S: Hash + Eq + Clone,
+    I: IntoIterator<Item = S>,
+{
+    fn count<S, I>(
+        state: &S,
+        successors: &mut impl FnMut(&S) -> I,
+        is_goal: &mut impl FnMut(&S) -> bool,
+        memo: &mut HashMap<S, u64>,
+    ) -> u64
+    where
+        S: Hash + Eq + Clone,
+        I: IntoIterator<Item = S>,
+    {
+        if let Some(known) = memo.get(state) {
+            return *known;
+        }
+        let res = if is_goal(state) {
+            1
+        } else {
+            successors(state)
+                .into_iter()
+                .map(|next| count(&next, successors, is_goal, memo))
+                .sum()
+        };
+        memo.insert(state.clone(), res);
+        res
+    }
+
+    count(&start, &mut successors, &mut is_goal, &mut HashMap::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{best_first, bfs, count_paths, dfs, dijkstra, find_cycle, reachable, walk_loops};
+
+    // 0 -> 1 -> 2 -> 3, 0 -> 4 -> 3, 5 is isolated
+    fn edges(node: &u32) -> Vec<u32> {
+        match node {
+            0 => vec![1, 4],
+            1 => vec![2],
+            2 => vec![3],
+            4 => vec![3],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn unweighted() {
+        assert_eq!(Some(vec![0, 1, 2, 3]), dfs(0, edges, |node| *node == 3));
+        assert_eq!(Some(vec![0, 4, 3]), bfs([0], edges, |node| *node == 3));
+        assert_eq!(None, bfs([1], edges, |node| *node == 4));
+        assert_eq!(Some(vec![5]), bfs([0, 5], edges, |node| *node == 5));
+        assert_eq!(3, reachable([1], edges).len());
+    }
+
+    #[test]
+    fn weighted() {
+        // collatz-ish walk to 1, halving is cheap and tripling is expensive
+        let successors = |n: &u64| {
+            let mut next = vec![(n - 1, 3)];
+            if n.is_multiple_of(2) {
+                next.push((n / 2, 1));
+            }
+            next
+        };
+        let (cost, path) = dijkstra([10], successors, |n| *n == 1).unwrap();
+        assert_eq!(6, cost);
+        assert_eq!(vec![10, 5, 4, 2, 1], path);
+
+        let (_, greedy) = best_first([10], successors, |n| *n == 1, |n, _| *n).unwrap();
+        assert_eq!(Some(&1), greedy.last());
+    }
+
+    #[test]
+    fn cycles() {
+        assert_eq!(None, find_cycle(0, edges));
+        let ring = |n: &u32| if *n < 5 { Some((n + 1) % 5) } else { None };
+        assert_eq!(Some(vec![0, 1, 2, 3, 4]), find_cycle(0, ring));
+        let lasso = |n: &u32| Some(if *n == 6 { 3 } else { n + 1 });
+        assert_eq!(Some(vec![3, 4, 5, 6]), find_cycle(0, lasso));
+
+        let mut seen = HashSet::new();
+        assert!(walk_loops(
+            0,
+            |n| Some(if *n == 6 { 3 } else { n + 1 }),
+            |n| seen.insert(*n)
+        ));
+        let mut seen = [false; 10];
+        let ends = |n: &usize| (*n < 9).then_some(n + 1);
+        assert!(!walk_loops(0, ends, |n| !std::mem::replace(
+            &mut seen[*n],
+            true
+        )));
+    }
+
+    #[test]
+    fn path_counting() {
+        assert_eq!(2, count_paths(0, edges, |node| *node == 3));
+        // lattice paths through a 10x10 grid
+        let moves = |pos: &(u32, u32)| {
+            let mut next = Vec::new();
+            if pos.0 < 10 {
+                next.push((pos.0 + 1, pos.1));
+            }
+            if pos.1 < 10 {
+                next.push((pos.0, pos.1 + 1));
+            }
+            next
+        };
+        assert_eq!(184756, count_paths((0, 0), moves, |pos| *pos == (10, 10)));
+    }
+}
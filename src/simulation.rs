@@ -0,0 +1,227 @@
+//! Step-by-step simulations and a driver to run them.
+
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use crate::{cycle::Cycle, grid::Grid};
+
+pub trait Simulation {
+    /// Snapshot of everything that decides how the simulation continues. Two
+    /// equal states must behave the same from then on.
+    type State: Hash + Eq;
+
+    fn step(&mut self);
+
+    /// Whether the simulation has finished on its own.
+    fn is_done(&self) -> bool {
+        false
+    }
+
+    fn render(&self) -> Grid<char>;
+
+    fn state(&self) -> Self::State;
+
+    fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.state().hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+type Hook<'a, S> = Box<dyn FnMut(usize, &S) + 'a>;
+
+/// Runs a [`Simulation`], counting steps and calling hooks after each one.
+pub struct Driver<'a, S: Simulation> {
+    simulation: S,
+    steps: usize,
+    hooks: Vec<Hook<'a, S>>,
+    frames: Option<Vec<Grid<char>>>,
+}
+
+impl<'a, S: Simulation> Driver<'a, S> {
+    pub fn new(simulation: S) -> Self {
+        Self {
+            simulation,
+            steps: 0,
+            hooks: Vec::new(),
+            frames: None,
+        }
+    }
+
+    /// Calls `hook` with the step count and simulation after every step.
+    pub fn on_step(mut self, hook: impl FnMut(usize, &S) + 'a) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Keeps a render of the starting state and of every step.
+    pub fn record_frames(mut self) -> Self {
+        self.frames = Some(vec![self.simulation.render()]);
+        self
+    }
+
+    pub fn frames(&self) -> &[Grid<char>] {
+        self.frames.as_deref().unwrap_or_default()
+    }
+
+    pub fn simulation(&self) -> &S {
+        &self.simulation
+    }
+
+    pub fn into_inner(self) -> S {
+        self.simulation
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    fn step(&mut self) {
+        self.simulation.step();
+        self.steps += 1;
+        for hook in &mut self.hooks {
+            hook(self.steps, &self.simulation);
+        }
+        if let Some(frames) = &mut self.frames {
+            frames.push(self.simulation.render());
+        }
+    }
+
+    /// Runs `n` steps, or fewer if the simulation finishes first.
+    pub fn run(&mut self, n: usize) -> &mut Self {
+        for _ in 0..n {
+            if self.simulation.is_done() {
+                break;
+            }
+            self.step();
+        }
+        self
+    }
+
+    /// Runs until the simulation finishes and returns the total step count.
+    /// Never returns for a simulation that doesn't finish.
+    pub fn run_to_end(&mut self) -> usize {
+        while !self.simulation.is_done() {
+            self.step();
+        }
+        self.steps
+    }
+
+    /// Runs until the simulation finishes, returning `None`, or until a state
+    /// repeats, returning the loop it is stuck in. Keeps every state seen.
+    pub fn detect_cycle(&mut self) -> Option<Cycle> {
+        let mut seen: HashMap<S::State, usize> = HashMap::new();
+        while !self.simulation.is_done() {
+            if let Some(start) = seen.insert(self.simulation.state(), self.steps) {
+                return Some(Cycle {
+                    start,
+                    length: self.steps - start,
+                });
+            }
+            self.step();
+        }
+        None
+    }
+
+    /// Brings the simulation to step `n`. Once a state repeats, whole laps of
+    /// the loop are skipped, so `n` can be far larger than could be stepped.
+    pub fn fast_forward(&mut self, n: usize) -> &mut Self {
+        let mut seen: HashMap<S::State, usize> = HashMap::new();
+        while self.steps < n && !self.simulation.is_done() {
+            if let Some(start) = seen.insert(self.simulation.state(), self.steps) {
+                let length = self.steps - start;
+                let remaining = (n - self.steps) % length;
+                // skipped laps don't run hooks, but the step count stays true
+                self.steps = n - remaining;
+                return self.run(remaining);
+            }
+            self.step();
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Driver, Simulation};
+    use crate::{cycle::Cycle, grid::Grid};
+
+    /// A light moving right along a row, wrapping at the end unless `stop` is set.
+    struct Light {
+        pos: usize,
+        width: usize,
+        stop: bool,
+    }
+
+    impl Simulation for Light {
+        type State = usize;
+
+        fn step(&mut self) {
+            self.pos = (self.pos + 1) % self.width;
+        }
+
+        fn is_done(&self) -> bool {
+            self.stop && self.pos == self.width - 1
+        }
+
+        fn render(&self) -> Grid<char> {
+            let mut grid = Grid::new(1, self.width, '.');
+            grid[(0, self.pos as i32)] = '*';
+            grid
+        }
+
+        fn state(&self) -> usize {
+            self.pos
+        }
+    }
+
+    fn light(stop: bool) -> Light {
+        Light {
+            pos: 0,
+            width: 4,
+            stop,
+        }
+    }
+
+    #[test]
+    fn runs_and_records() {
+        let mut seen = Vec::new();
+        let mut driver = Driver::new(light(true))
+            .record_frames()
+            .on_step(|step, light| seen.push((step, light.pos)));
+        assert_eq!(3, driver.run_to_end());
+        let frames: Vec<_> = driver
+            .frames()
+            .iter()
+            .map(|frame| frame.to_string())
+            .collect();
+        assert_eq!(vec!["*...\n", ".*..\n", "..*.\n", "...*\n"], frames);
+        drop(driver);
+        assert_eq!(vec![(1, 1), (2, 2), (3, 3)], seen);
+    }
+
+    #[test]
+    fn loops_and_fast_forward() {
+        let mut driver = Driver::new(light(false));
+        assert_eq!(
+            Some(Cycle {
+                start: 0,
+                length: 4
+            }),
+            driver.detect_cycle()
+        );
+
+        let mut driver = Driver::new(light(false));
+        driver.fast_forward(1_000_000_000_001);
+        assert_eq!(1_000_000_000_001, driver.steps());
+        assert_eq!(1, driver.simulation().pos);
+        assert_ne!(light(false).state_hash(), driver.simulation().state_hash());
+
+        let mut driver = Driver::new(light(true));
+        assert_eq!(None, driver.detect_cycle());
+        driver.run(10);
+        assert_eq!(3, driver.steps());
+    }
+}
@@ -0,0 +1,314 @@
+//! Rotated, mirrored and transposed views of a grid, and the lines through it.
+
+use crate::grid::Grid;
+
+/// One of the eight symmetries of a rectangle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Transform {
+    Identity,
+    /// 90° clockwise.
+    Rotate90,
+    Rotate180,
+    /// 270° clockwise, i.e. 90° counter-clockwise.
+    Rotate270,
+    /// Mirrored along the main diagonal, rows become columns.
+    Transpose,
+    /// Mirrored along the anti-diagonal.
+    AntiTranspose,
+    /// Mirrored left to right.
+    FlipHorizontal,
+    /// Mirrored top to bottom.
+    FlipVertical,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 8] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+        Transform::Transpose,
+        Transform::AntiTranspose,
+        Transform::FlipHorizontal,
+        Transform::FlipVertical,
+    ];
+
+    pub const ROTATIONS: [Transform; 4] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+    ];
+
+    /// Whether the view swaps height and width.
+    pub fn swaps_axes(&self) -> bool {
+        matches!(
+            self,
+            Transform::Rotate90
+                | Transform::Rotate270
+                | Transform::Transpose
+                | Transform::AntiTranspose
+        )
+    }
+
+    /// Size of the transformed view of a `height` x `width` grid.
+    pub fn size(&self, height: usize, width: usize) -> (usize, usize) {
+        if self.swaps_axes() {
+            (width, height)
+        } else {
+            (height, width)
+        }
+    }
+
+    /// Maps a position in the transformed view back to the source grid of
+    /// size `height` x `width`.
+    pub fn source(&self, pos: (i32, i32), height: usize, width: usize) -> (i32, i32) {
+        let (h, w) = (height as i32, width as i32);
+        let (y, x) = pos;
+        match self {
+            Transform::Identity => (y, x),
+            Transform::Rotate90 => (h - 1 - x, y),
+            Transform::Rotate180 => (h - 1 - y, w - 1 - x),
+            Transform::Rotate270 => (x, w - 1 - y),
+            Transform::Transpose => (x, y),
+            Transform::AntiTranspose => (h - 1 - x, w - 1 - y),
+            Transform::FlipHorizontal => (y, w - 1 - x),
+            Transform::FlipVertical => (h - 1 - y, x),
+        }
+    }
+}
+
+/// Borrowed view of a grid under a [`Transform`]; nothing is copied.
+#[derive(Clone, Copy, Debug)]
+pub struct GridView<'a, T> {
+    grid: &'a Grid<T>,
+    transform: Transform,
+}
+
+impl<'a, T> GridView<'a, T> {
+    pub fn new(grid: &'a Grid<T>, transform: Transform) -> Self {
+        Self { grid, transform }
+    }
+
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    pub fn height(&self) -> usize {
+        self.transform.size(self.grid.height(), self.grid.width()).0
+    }
+
+    pub fn width(&self) -> usize {
+        self.transform.size(self.grid.height(), self.grid.width()).1
+    }
+
+    pub fn in_bounds(&self, pos: (i32, i32)) -> bool {
+        pos.0 >= 0
+            && pos.1 >= 0
+            && (pos.0 as usize) < self.height()
+            && (pos.1 as usize) < self.width()
+    }
+
+    /// Position in the underlying grid of a position in this view.
+    pub fn source(&self, pos: (i32, i32)) -> (i32, i32) {
+        self.transform
+            .source(pos, self.grid.height(), self.grid.width())
+    }
+
+    pub fn get(&self, pos: (i32, i32)) -> Option<&'a T> {
+        if !self.in_bounds(pos) {
+            return None;
+        }
+        self.grid.get(self.source(pos))
+    }
+
+    pub fn lines(&self, kind: LineKind) -> impl Iterator<Item = Line> {
+        lines(self.height(), self.width(), kind)
+    }
+
+    /// Values along `line`, which must come from this view's [`Self::lines`].
+    pub fn line_values(&self, line: Line) -> impl Iterator<Item = &'a T> + '_ {
+        line.map(|pos| self.get(pos).expect("line runs outside the view"))
+    }
+
+    /// Copies the view into a new grid.
+    pub fn to_grid(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let (height, width) = (self.height(), self.width());
+        let cells = (0..height as i32)
+            .flat_map(|y| (0..width as i32).map(move |x| (y, x)))
+            .map(|pos| self.get(pos).unwrap().clone())
+            .collect();
+        Grid::from_vec(height, width, cells)
+    }
+}
+
+impl GridView<'_, char> {
+    pub fn line_string(&self, line: Line) -> String {
+        self.line_values(line).collect()
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn view(&self, transform: Transform) -> GridView<'_, T> {
+        GridView::new(self, transform)
+    }
+
+    pub fn lines(&self, kind: LineKind) -> impl Iterator<Item = Line> {
+        lines(self.height(), self.width(), kind)
+    }
+}
+
+/// Families of straight lines through a grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LineKind {
+    /// Left to right.
+    Rows,
+    /// Top to bottom.
+    Columns,
+    /// Top-left to bottom-right.
+    Diagonals,
+    /// Top-right to bottom-left.
+    AntiDiagonals,
+}
+
+impl LineKind {
+    pub const ALL: [LineKind; 4] = [
+        LineKind::Rows,
+        LineKind::Columns,
+        LineKind::Diagonals,
+        LineKind::AntiDiagonals,
+    ];
+
+    pub fn step(&self) -> (i32, i32) {
+        match self {
+            LineKind::Rows => (0, 1),
+            LineKind::Columns => (1, 0),
+            LineKind::Diagonals => (1, 1),
+            LineKind::AntiDiagonals => (1, -1),
+        }
+    }
+}
+
+/// Positions along one line, starting at `start` and moving by `step`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Line {
+    pub start: (i32, i32),
+    pub step: (i32, i32),
+    pub len: usize,
+}
+
+impl Line {
+    /// Position of the `index`th cell of the line.
+    pub fn at(&self, index: usize) -> (i32, i32) {
+        let index = index as i32;
+        (
+            self.start.0 + self.step.0 * index,
+            self.start.1 + self.step.1 * index,
+        )
+    }
+}
+
+impl Iterator for Line {
+    type Item = (i32, i32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let pos = self.start;
+        self.start = self.at(1);
+        self.len -= 1;
+        Some(pos)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl ExactSizeIterator for Line {}
+
+/// Every line of the given kind through a `height` x `width` grid.
+pub fn lines(height: usize, width: usize, kind: LineKind) -> impl Iterator<Item = Line> {
+    let (h, w) = (height as i32, width as i32);
+    let step = kind.step();
+    let starts: Vec<(i32, i32)> = match kind {
+        LineKind::Rows => (0..h).map(|y| (y, 0)).collect(),
+        LineKind::Columns => (0..w).map(|x| (0, x)).collect(),
+        LineKind::Diagonals if w > 0 => (0..h)
+            .rev()
+            .map(|y| (y, 0))
+            .chain((1..w).map(|x| (0, x)))
+            .collect(),
+        LineKind::AntiDiagonals if h > 0 => (0..w)
+            .map(|x| (0, x))
+            .chain((1..h).map(|y| (y, w - 1)))
+            .collect(),
+        _ => Vec::new(),
+    };
+    starts.into_iter().map(move |start| {
+        let len = match kind {
+            LineKind::Rows => w,
+            LineKind::Columns => h,
+            LineKind::Diagonals => (h - start.0).min(w - start.1),
+            LineKind::AntiDiagonals => (h - start.0).min(start.1 + 1),
+        };
+        Line {
+            start,
+            step,
+            len: len as usize,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineKind, Transform};
+    use crate::grid::Grid;
+
+    fn grid() -> Grid<char> {
+        Grid::parse("abc\ndef", |char| char)
+    }
+
+    #[test]
+    fn views() {
+        let grid = grid();
+        let render = |transform| grid.view(transform).to_grid().to_string();
+        assert_eq!("abc\ndef\n", render(Transform::Identity));
+        assert_eq!("da\neb\nfc\n", render(Transform::Rotate90));
+        assert_eq!("fed\ncba\n", render(Transform::Rotate180));
+        assert_eq!("cf\nbe\nad\n", render(Transform::Rotate270));
+        assert_eq!("ad\nbe\ncf\n", render(Transform::Transpose));
+        assert_eq!("fc\neb\nda\n", render(Transform::AntiTranspose));
+        assert_eq!("cba\nfed\n", render(Transform::FlipHorizontal));
+        assert_eq!("def\nabc\n", render(Transform::FlipVertical));
+
+        let rotated = grid.view(Transform::Rotate90);
+        assert_eq!((3, 2), (rotated.height(), rotated.width()));
+        assert_eq!(Some(&'d'), rotated.get((0, 0)));
+        assert_eq!(None, rotated.get((0, 2)));
+    }
+
+    #[test]
+    fn lines() {
+        let grid = grid();
+        let view = grid.view(Transform::Identity);
+        let strings = |kind| {
+            view.lines(kind)
+                .map(|line| view.line_string(line))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["abc", "def"], strings(LineKind::Rows));
+        assert_eq!(vec!["ad", "be", "cf"], strings(LineKind::Columns));
+        assert_eq!(vec!["d", "ae", "bf", "c"], strings(LineKind::Diagonals));
+        assert_eq!(vec!["a", "bd", "ce", "f"], strings(LineKind::AntiDiagonals));
+
+        // every cell shows up exactly once per kind of line
+        for kind in LineKind::ALL {
+            assert_eq!(6, grid.lines(kind).map(|line| line.len).sum::<usize>());
+        }
+    }
+}
@@ -0,0 +1,325 @@
+//! Word search in letter grids, in all eight directions.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use aho_corasick::AhoCorasick;
+use rayon::prelude::*;
+
+use crate::{
+    grid::Grid,
+    transform::{Line, LineKind},
+};
+
+/// Grid rows searched by one rayon task.
+pub const ROWS_PER_BAND: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WordMatch {
+    /// Index into the searched word list.
+    pub word: usize,
+    pub start: (i32, i32),
+    pub direction: (i32, i32),
+}
+
+impl WordMatch {
+    /// Last cell of the match, for a word of `length` letters.
+    pub fn end(&self, length: usize) -> (i32, i32) {
+        let steps = length as i32 - 1;
+        (
+            self.start.0 + self.direction.0 * steps,
+            self.start.1 + self.direction.1 * steps,
+        )
+    }
+}
+
+/// A grid cell the search reads. ASCII grids are searched as bytes, in place
+/// where the line is a row; `char` is the fallback for grids that aren't.
+pub trait Letter: Copy + PartialEq + Send + Sync {
+    /// The letters as the searched text, if that is what they already are.
+    fn as_bytes(letters: &[Self]) -> Option<&[u8]>;
+    /// Appends the letter as UTF-8.
+    fn encode(self, text: &mut Vec<u8>);
+    /// Letters in a word, which is UTF-8 unless the grid holds bytes.
+    fn count(word: &[u8]) -> usize;
+    fn reversed(word: &[u8]) -> Vec<u8>;
+}
+
+impl Letter for u8 {
+    fn as_bytes(letters: &[Self]) -> Option<&[u8]> {
+        Some(letters)
+    }
+
+    fn encode(self, text: &mut Vec<u8>) {
+        text.push(self);
+    }
+
+    fn count(word: &[u8]) -> usize {
+        word.len()
+    }
+
+    fn reversed(word: &[u8]) -> Vec<u8> {
+        word.iter().rev().copied().collect()
+    }
+}
+
+impl Letter for char {
+    fn as_bytes(_: &[Self]) -> Option<&[u8]> {
+        None
+    }
+
+    fn encode(self, text: &mut Vec<u8>) {
+        text.extend_from_slice(self.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    fn count(word: &[u8]) -> usize {
+        String::from_utf8_lossy(word).chars().count()
+    }
+
+    fn reversed(word: &[u8]) -> Vec<u8> {
+        let reversed: String = String::from_utf8_lossy(word).chars().rev().collect();
+        reversed.into_bytes()
+    }
+}
+
+/// The input as a byte grid, if it is ASCII. Otherwise parse a `Grid<char>`.
+pub fn ascii_grid(input: &str) -> Option<Grid<u8>> {
+    input
+        .is_ascii()
+        .then(|| Grid::parse(input, |char| char as u8))
+}
+
+/// Finds every occurrence of every word in all eight directions, overlaps
+/// included, sorted. A word that reads the same backwards is reported once
+/// per set of cells it covers, and a single letter once, going nowhere
+/// (direction `(0, 0)`).
+///
+/// Bands of `rows_per_band` rows are searched in parallel, each for the
+/// matches starting in it, and the result doesn't depend on the band size.
+pub fn find_words<T: Letter>(
+    grid: &Grid<T>,
+    words: &[impl AsRef<[u8]>],
+    rows_per_band: usize,
+) -> Vec<WordMatch> {
+    let search = Search::new::<T>(words);
+    let mut matches: Vec<WordMatch> = grid
+        .row_bands(rows_per_band)
+        .into_par_iter()
+        .flat_map_iter(|rows| search.in_rows(grid, rows))
+        .collect();
+    matches.sort();
+
+    // palindromes turn up reading both ways, single letters along every line
+    let mut seen = HashSet::new();
+    matches.retain(|hit| {
+        let start = hit.start;
+        let end = hit.end(search.lengths[hit.word]);
+        seen.insert((hit.word, start.min(end), start.max(end)))
+    });
+    matches
+}
+
+/// All words and their reversals in one automaton, so each line is scanned
+/// once for everything.
+struct Search {
+    automaton: AhoCorasick,
+    /// For each automaton pattern the words it stands for, and whether
+    /// reversed. A pattern may be a word and another word reversed.
+    patterns: Vec<Vec<(usize, bool)>>,
+    /// Letters in each word.
+    lengths: Vec<usize>,
+}
+
+impl Search {
+    fn new<T: Letter>(words: &[impl AsRef<[u8]>]) -> Self {
+        let lengths: Vec<usize> = words.iter().map(|word| T::count(word.as_ref())).collect();
+        let mut patterns: HashMap<Vec<u8>, Vec<(usize, bool)>> = HashMap::new();
+        for (index, word) in words.iter().enumerate() {
+            let word = word.as_ref();
+            if word.is_empty() {
+                continue;
+            }
+            patterns
+                .entry(word.to_vec())
+                .or_default()
+                .push((index, false));
+            // a single letter has no direction to read it backwards in
+            if lengths[index] > 1 {
+                let reversed = T::reversed(word);
+                patterns.entry(reversed).or_default().push((index, true));
+            }
+        }
+        let (texts, patterns): (Vec<_>, Vec<_>) = patterns.into_iter().unzip();
+        Self {
+            automaton: AhoCorasick::new(&texts).unwrap(),
+            patterns,
+            lengths,
+        }
+    }
+
+    /// Matches starting in `rows`. Only the parts of the lines a match
+    /// starting there can reach are scanned.
+    fn in_rows<T: Letter>(&self, grid: &Grid<T>, rows: Range<usize>) -> Vec<WordMatch> {
+        let longest = self.lengths.iter().copied().max().unwrap_or(0);
+        let reach = longest.saturating_sub(1) as i32;
+        let (first, last) = (rows.start as i32, rows.end as i32 - 1);
+        let mut text = Vec::new();
+        let mut cell_starts = Vec::new();
+        let mut matches = Vec::new();
+        for kind in LineKind::ALL {
+            for line in grid.lines(kind) {
+                // every kind of line runs along a row or downwards
+                let (from, to) = match line.step.0 {
+                    0 if (first..=last).contains(&line.start.0) => (0, line.len),
+                    0 => continue,
+                    _ => (
+                        (first - reach - line.start.0).clamp(0, line.len as i32) as usize,
+                        (last + reach - line.start.0 + 1).clamp(0, line.len as i32) as usize,
+                    ),
+                };
+                if from == to {
+                    continue;
+                }
+                let segment = Line {
+                    start: line.at(from),
+                    step: line.step,
+                    len: to - from,
+                };
+                if kind == LineKind::Rows {
+                    if let Some(row) = T::as_bytes(grid.row(segment.start.0 as usize)) {
+                        self.in_line(row, None, segment, &mut matches);
+                        continue;
+                    }
+                }
+                text.clear();
+                cell_starts.clear();
+                for pos in segment {
+                    cell_starts.push(text.len());
+                    grid[pos].encode(&mut text);
+                }
+                // offsets are cells already when every letter is one byte
+                let starts = (text.len() != segment.len).then_some(&cell_starts[..]);
+                self.in_line(&text, starts, segment, &mut matches);
+            }
+        }
+        matches.retain(|hit| (first..=last).contains(&hit.start.0));
+        matches
+    }
+
+    /// Matches along `line`, whose letters are `text`. `cell_starts` maps
+    /// byte offsets to cells when letters take more than one byte.
+    fn in_line(
+        &self,
+        text: &[u8],
+        cell_starts: Option<&[usize]>,
+        line: Line,
+        matches: &mut Vec<WordMatch>,
+    ) {
+        let cell = |offset: usize| match cell_starts {
+            Some(starts) => starts.partition_point(|&start| start < offset),
+            None => offset,
+        };
+        for hit in self.automaton.find_overlapping_iter(text) {
+            for (word, reversed) in &self.patterns[hit.pattern().as_usize()] {
+                let (start, direction) = if *reversed {
+                    (line.at(cell(hit.end()) - 1), (-line.step.0, -line.step.1))
+                } else if self.lengths[*word] == 1 {
+                    (line.at(cell(hit.start())), (0, 0))
+                } else {
+                    (line.at(cell(hit.start())), line.step)
+                };
+                matches.push(WordMatch {
+                    word: *word,
+                    start,
+                    direction,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ascii_grid, find_words};
+    use crate::grid::Grid;
+
+    #[test]
+    fn word_search() {
+        let grid = ascii_grid("ABA\nBAB\nXYZ").unwrap();
+        let words = ["ABA".to_string(), "BA".to_string(), "ZYX".to_string()];
+        let matches = find_words(&grid, &words, 1);
+
+        let found = |word: usize| {
+            matches
+                .iter()
+                .filter(|hit| hit.word == word)
+                .map(|hit| (hit.start, hit.direction))
+                .collect::<Vec<_>>()
+        };
+        // palindromes are found once, not once per way they can be read
+        assert_eq!(vec![((0, 0), (0, 1))], found(0));
+        assert_eq!(
+            vec![
+                ((0, 1), (0, -1)),
+                ((0, 1), (0, 1)),
+                ((0, 1), (1, 0)),
+                ((1, 0), (-1, 0)),
+                ((1, 0), (0, 1)),
+                ((1, 2), (-1, 0)),
+                ((1, 2), (0, -1)),
+            ],
+            found(1)
+        );
+        assert_eq!(vec![((2, 2), (0, -1))], found(2));
+    }
+
+    #[test]
+    fn unicode_and_single_letters() {
+        assert_eq!(None, ascii_grid("ŘXé\nAéB\néCD"));
+        let grid = Grid::parse("ŘXé\nAéB\néCD", |char| char);
+        let words = ["é".to_string(), "XéC".to_string(), "BéA".to_string()];
+        let found: Vec<_> = find_words(&grid, &words, 2)
+            .iter()
+            .map(|hit| (hit.word, hit.start, hit.direction))
+            .collect();
+        assert_eq!(
+            vec![
+                (0, (0, 2), (0, 0)),
+                (0, (1, 1), (0, 0)),
+                (0, (2, 0), (0, 0)),
+                (1, (0, 1), (1, 0)),
+                (2, (1, 2), (0, -1)),
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn bands_dont_change_matches() {
+        // pseudo-random XMAS letters, so words cross the bands in every direction
+        let mut seed = 3_u64;
+        let input: String = (0..60)
+            .map(|_| {
+                (0..45)
+                    .map(|_| {
+                        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                        ['X', 'M', 'A', 'S'][(seed >> 62) as usize]
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let grid = ascii_grid(&input).unwrap();
+        let words: [&[u8]; 3] = [b"XMAS", b"AM", b"SAS"];
+        let whole = find_words(&grid, &words, grid.height());
+        assert!(whole.len() > 100);
+        for rows_per_band in [1, 2, 3, 7, 64] {
+            assert_eq!(whole, find_words(&grid, &words, rows_per_band));
+        }
+        // the char fallback finds the same
+        let grid = Grid::parse(&input, |char| char);
+        assert_eq!(whole, find_words(&grid, &words, 7));
+    }
+}